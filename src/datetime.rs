@@ -1,8 +1,8 @@
 use {
     crate::use_m::*,
-    chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone, Utc},
+    chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc},
     python_comm_macros::auto_func_name,
-    std::time,
+    std::{iter, time},
 };
 
 // https://docs.python.org/3/library/datetime.html#datetime.datetime.fromisoformat
@@ -46,6 +46,25 @@ pub fn bj_dates() -> String {
     bjtc_ds(&bj_date())
 }
 
+/// Milliseconds since Unix epoch, same as bj_timestamp_millis
+///
+/// The epoch is timezone independent, so the value equals the UTC epoch millis,
+/// "Beijing time" only matters when converting it back to a date or time.
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+///
+/// let ts = bj_now_ms();
+/// assert!(ts > 1623913021000 && ts <= bj_timestamp_millis());
+/// ```
+///
+#[inline]
+pub fn bj_now_ms() -> i64 {
+    bj_timestamp_millis()
+}
+
 /// Beijing time, date and time
 ///
 /// ## Usage
@@ -62,6 +81,40 @@ pub fn bj_time() -> DateTime<FixedOffset> {
     Utc::now().with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
 }
 
+/// Beijing time, the date at hour:min:sec, panic if the time is invalid like bj_time_init
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+/// assert_eq!(bj_time_from_date(date, 9, 30, 0), bj_time_init(2024, 1, 15, 9, 30, 0));
+/// ```
+///
+#[inline]
+pub fn bj_time_from_date(date: NaiveDate, hour: u32, min: u32, sec: u32) -> DateTime<FixedOffset> {
+    bj_time_init(date.year(), date.month(), date.day(), hour, min, sec)
+}
+
+/// Beijing time, from a timestamp integer string, see bjtc_nt
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+///
+/// assert_eq!(bj_time_from_timestamp_str("1705282200").unwrap(), bj_time_init(2024, 1, 15, 9, 30, 0));
+/// assert!(bj_time_from_timestamp_str("2024-01-15").is_err());
+/// ```
+///
+#[auto_func_name]
+pub fn bj_time_from_timestamp_str(s: &str) -> Result<DateTime<FixedOffset>, MoreError> {
+    let timestamp = s.trim().parse::<i64>().f(m!(fname, || format!("s={}", s)))?;
+    bjtc_nt(timestamp, 0).m(m!(fname))
+}
+
 #[inline]
 pub fn bj_time_init(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> DateTime<FixedOffset> {
     FixedOffset::east_opt(8 * 3600)
@@ -71,6 +124,47 @@ pub fn bj_time_init(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u
         .unwrap()
 }
 
+/// Beijing time, 00:00:00 of the date, error if the date is invalid
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+///
+/// assert_eq!(bjtc_from_ymd(2024, 2, 29).unwrap(), bj_time_init(2024, 2, 29, 0, 0, 0));
+/// assert_eq!(bjtc_from_ymd_hms(2024, 2, 29, 9, 30, 0).unwrap(), bj_time_init(2024, 2, 29, 9, 30, 0));
+/// assert!(bjtc_from_ymd(2023, 2, 29).is_err());
+/// ```
+///
+#[inline]
+#[auto_func_name]
+pub fn bjtc_from_ymd(year: i32, month: u32, day: u32) -> Result<DateTime<FixedOffset>, MoreError> {
+    bjtc_from_ymd_hms(year, month, day, 0, 0, 0).m(m!(fname))
+}
+
+/// Beijing time, error if the date or time is invalid, see bjtc_from_ymd
+#[auto_func_name]
+pub fn bjtc_from_ymd_hms(
+    year: i32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    min: u32,
+    sec: u32,
+) -> Result<DateTime<FixedOffset>, MoreError> {
+    FixedOffset::east_opt(8 * 3600)
+        .unwrap()
+        .with_ymd_and_hms(year, month, day, hour, min, sec)
+        .single()
+        .ok_or_else(|| {
+            m!(
+                fname,
+                &format!("{}-{}-{} {}:{}:{}", year, month, day, hour, min, sec),
+                "more"
+            )
+        })
+}
+
 /// Beijing time, date and time, %Y-%m-%d %H:%M:%S format string
 ///
 /// ## Usage
@@ -117,6 +211,22 @@ pub fn bj_timestamp() -> i64 {
     bj_time().timestamp()
 }
 
+/// Beijing time, timestamp, accurate to microseconds
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+///
+/// let ts = bj_timestamp_micros();
+/// assert!(ts > 1623913021000000 && ts < 1623913021000000 + 86400000000 * 36500);
+/// ```
+///
+#[inline]
+pub fn bj_timestamp_micros() -> i64 {
+    bj_time().timestamp_micros()
+}
+
 /// Beijing time, timestamp, accurate to milliseconds
 ///
 /// ## Usage
@@ -300,6 +410,15 @@ pub fn bjtc_nt(timestamp: i64, millis: u32) -> Result<DateTime<FixedOffset>, Mor
         .ok_or(m!(fname, &format!("timestamp={}", timestamp), "more"))
 }
 
+/// Same as bjtc_nt, but accurate to microseconds
+#[inline]
+#[auto_func_name]
+pub fn bjtc_nt_micros(timestamp: i64, micros: u32) -> Result<DateTime<FixedOffset>, MoreError> {
+    DateTime::from_timestamp(timestamp, micros * 1000)
+        .map(|t| t.with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap()))
+        .ok_or(m!(fname, &format!("timestamp={}", timestamp), "more"))
+}
+
 // sx
 
 /// See bjtc_bd
@@ -341,6 +460,49 @@ pub fn bjtc_st(text: &str) -> Result<DateTime<FixedOffset>, MoreError> {
     DateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%:z").m(m!(fname, text))
 }
 
+/// Same as bjtc_st, but text without timezone is assumed to be +08:00
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+///
+/// let t = bj_time_init(2024, 1, 15, 9, 0, 0);
+/// assert_eq!(bjtc_st_assume_bj("2024-01-15T09:00:00").unwrap(), t);
+/// assert_eq!(bjtc_st_assume_bj("2024-01-15T09:00:00+08:00").unwrap(), t);
+/// ```
+///
+#[auto_func_name]
+pub fn bjtc_st_assume_bj(text: &str) -> Result<DateTime<FixedOffset>, MoreError> {
+    match bjtc_st(text) {
+        Ok(time) => Ok(time),
+        Err(_) => bjtc_bt(text).m(m!(fname)),
+    }
+}
+
+/// Parse MySQL DATETIME format %Y-%m-%d %H:%M:%S as Beijing time, see bjtc_ts_naive
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+///
+/// let t = bjtc_st_naive("2024-01-15 09:30:00").unwrap();
+/// assert_eq!(t, bj_time_init(2024, 1, 15, 9, 30, 0));
+/// assert_eq!(bjtc_ts_naive(&t), "2024-01-15 09:30:00");
+/// assert!(bjtc_st_naive("2024-01-15T09:30:00").is_err());
+/// ```
+///
+#[auto_func_name]
+pub fn bjtc_st_naive(text: &str) -> Result<DateTime<FixedOffset>, MoreError> {
+    let time = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").m(m!(fname, text))?;
+    FixedOffset::east_opt(8 * 3600)
+        .unwrap()
+        .from_local_datetime(&time)
+        .single()
+        .ok_or_else(|| m!(fname, text, "more"))
+}
+
 // tx
 
 /// See bjtc_bd
@@ -373,6 +535,12 @@ pub fn bjtc_ts(time: &DateTime<FixedOffset>) -> String {
     bjtc_tt(time).format("%Y-%m-%dT%H:%M:%S%:z").to_string()
 }
 
+/// Format as Beijing time in MySQL DATETIME format %Y-%m-%d %H:%M:%S, see bjtc_st_naive
+#[inline]
+pub fn bjtc_ts_naive(time: &DateTime<FixedOffset>) -> String {
+    bjtc_tt(time).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
 /// See bjtc_tt
 #[inline]
 pub fn bjtc_tt(time: &DateTime<FixedOffset>) -> DateTime<FixedOffset> {
@@ -381,7 +549,305 @@ pub fn bjtc_tt(time: &DateTime<FixedOffset>) -> DateTime<FixedOffset> {
 
 //
 
-/// Convert duration to timestamp, accurate to milliseconds
+/// Add n days to date, saturating at NaiveDate::MIN / NaiveDate::MAX
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+/// assert_eq!(bjtc_add_days(&date, 1), NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+/// assert_eq!(bjtc_sub_days(&date, 31), NaiveDate::from_ymd_opt(2023, 12, 31).unwrap());
+/// ```
+///
+pub fn bjtc_add_days(date: &NaiveDate, n: i64) -> NaiveDate {
+    chrono::Duration::try_days(n)
+        .and_then(|dur| date.checked_add_signed(dur))
+        .unwrap_or(if n >= 0 { NaiveDate::MAX } else { NaiveDate::MIN })
+}
+
+/// Add n hours to time, saturating at the earliest / latest representable time
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+///
+/// let time = bj_time_init(2024, 1, 31, 23, 0, 0);
+/// assert_eq!(bjtc_add_hours(&time, 2), bj_time_init(2024, 2, 1, 1, 0, 0));
+/// assert_eq!(bjtc_sub_hours(&time, 24), bj_time_init(2024, 1, 30, 23, 0, 0));
+/// ```
+///
+pub fn bjtc_add_hours(time: &DateTime<FixedOffset>, n: i64) -> DateTime<FixedOffset> {
+    chrono::Duration::try_hours(n)
+        .and_then(|dur| time.checked_add_signed(dur))
+        .unwrap_or_else(|| {
+            let limit = if n >= 0 {
+                DateTime::<Utc>::MAX_UTC
+            } else {
+                DateTime::<Utc>::MIN_UTC
+            };
+            limit.with_timezone(time.offset())
+        })
+}
+
+/// Subtract n days from date, see bjtc_add_days
+#[inline]
+pub fn bjtc_sub_days(date: &NaiveDate, n: i64) -> NaiveDate {
+    bjtc_add_days(date, n.saturating_neg())
+}
+
+/// Subtract n hours from time, see bjtc_add_hours
+#[inline]
+pub fn bjtc_sub_hours(time: &DateTime<FixedOffset>, n: i64) -> DateTime<FixedOffset> {
+    bjtc_add_hours(time, n.saturating_neg())
+}
+
+//
+
+/// Format time with any chrono format string
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// let time = bj_time_init(2024, 1, 15, 9, 30, 0);
+/// assert_eq!(bjtc_format(&time, "%Y/%m/%d %H:%M %z"), "2024/01/15 09:30 +0800");
+/// assert_eq!(bjtc_parse("2024/01/15 09:30 +0800", "%Y/%m/%d %H:%M %z").unwrap(), time);
+///
+/// let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+/// assert_eq!(bjtc_format_date(&date, "%Y%m%d"), "20240115");
+/// ```
+///
+#[inline]
+pub fn bjtc_format(time: &DateTime<FixedOffset>, fmt: &str) -> String {
+    time.format(fmt).to_string()
+}
+
+/// Format date with any chrono format string, see bjtc_format
+#[inline]
+pub fn bjtc_format_date(date: &NaiveDate, fmt: &str) -> String {
+    date.format(fmt).to_string()
+}
+
+/// Parse time with any chrono format string, which must contain the timezone, see bjtc_format
+#[inline]
+#[auto_func_name]
+pub fn bjtc_parse(text: &str, fmt: &str) -> Result<DateTime<FixedOffset>, MoreError> {
+    DateTime::parse_from_str(text, fmt).f(m!(fname, || format!("text={}, fmt={}", text, fmt)))
+}
+
+/// Parse date with the first matching format in formats, see bjtc_parse_flexible
+#[auto_func_name]
+pub fn bjtc_parse_date_flexible(text: &str, formats: &[&str]) -> Result<NaiveDate, MoreError> {
+    match formats.iter().find_map(|fmt| NaiveDate::parse_from_str(text, fmt).ok()) {
+        Some(date) => Ok(date),
+        None => m!(fname, &format!("text={}, formats={:?}", text, formats), "result"),
+    }
+}
+
+/// Parse time with the first matching format in formats, see bjtc_parse
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// let formats = ["%Y/%m/%d", "%d-%m-%Y", "%Y.%m.%d"];
+/// let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+/// assert_eq!(bjtc_parse_date_flexible("15-01-2024", &formats).unwrap(), date);
+/// assert!(bjtc_parse_date_flexible("2024-01-15", &formats).is_err());
+///
+/// let formats = ["%Y/%m/%d %H:%M %z", "%Y-%m-%dT%H:%M:%S%:z"];
+/// let time = bjtc_parse_flexible("2024-01-15T09:00:00+08:00", &formats).unwrap();
+/// assert_eq!(time, bj_time_init(2024, 1, 15, 9, 0, 0));
+/// ```
+///
+#[auto_func_name]
+pub fn bjtc_parse_flexible(text: &str, formats: &[&str]) -> Result<DateTime<FixedOffset>, MoreError> {
+    match formats.iter().find_map(|fmt| DateTime::parse_from_str(text, fmt).ok()) {
+        Some(time) => Ok(time),
+        None => m!(fname, &format!("text={}, formats={:?}", text, formats), "result"),
+    }
+}
+
+//
+
+/// Number of days in the month, 28 ~ 31, 0 if month is not 1 ~ 12
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// assert_eq!(bjtc_days_in_month(2024, 2), 29);
+/// assert_eq!(bjtc_days_in_month(2023, 2), 28);
+/// assert!(bjtc_is_leap_year(2000));
+/// assert!(!bjtc_is_leap_year(1900));
+/// assert_eq!(bjtc_last_day_of_month(2024, 2), NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+/// ```
+///
+pub fn bjtc_days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if bjtc_is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Leap year: divisible by 4 but not by 100, or divisible by 400, see bjtc_days_in_month
+#[inline]
+pub fn bjtc_is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Last day of the month, panic if month is not 1 ~ 12, see bjtc_days_in_month
+#[inline]
+pub fn bjtc_last_day_of_month(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, bjtc_days_in_month(year, month)).unwrap()
+}
+
+/// Quarter of date, 1 ~ 4
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// let date = NaiveDate::from_ymd_opt(2024, 5, 20).unwrap();
+/// assert_eq!(bjtc_quarter(&date), 2);
+/// assert_eq!(bjtc_quarter_start(&date), NaiveDate::from_ymd_opt(2024, 4, 1).unwrap());
+/// assert_eq!(bjtc_quarter_end(&date), NaiveDate::from_ymd_opt(2024, 6, 30).unwrap());
+/// ```
+///
+#[inline]
+pub fn bjtc_quarter(date: &NaiveDate) -> u32 {
+    (date.month() - 1) / 3 + 1
+}
+
+/// Last day of the quarter containing date, see bjtc_quarter
+pub fn bjtc_quarter_end(date: &NaiveDate) -> NaiveDate {
+    bjtc_last_day_of_month(date.year(), bjtc_quarter(date) * 3)
+}
+
+/// First day of the quarter containing date, see bjtc_quarter
+pub fn bjtc_quarter_start(date: &NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd_opt(date.year(), (bjtc_quarter(date) - 1) * 3 + 1, 1).unwrap()
+}
+
+/// ISO 8601 week number of date, 1 ~ 53
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+/// assert_eq!(bjtc_week_of_year(&date), 1);
+/// assert_eq!(bjtc_weekday_num(&date), 1);
+/// assert_eq!(bjtc_weekday_cn(&date), "周一");
+/// ```
+///
+#[inline]
+pub fn bjtc_week_of_year(date: &NaiveDate) -> u32 {
+    date.iso_week().week()
+}
+
+/// (Monday, Sunday) of the ISO 8601 week containing date, see bjtc_week_of_year, saturating at NaiveDate::MIN / MAX
+pub fn bjtc_week_range(date: &NaiveDate) -> (NaiveDate, NaiveDate) {
+    let days = date.weekday().num_days_from_monday() as i64;
+    (bjtc_sub_days(date, days), bjtc_add_days(date, 6 - days))
+}
+
+/// Chinese weekday name of date, "周一" ~ "周日", see bjtc_week_of_year
+pub fn bjtc_weekday_cn(date: &NaiveDate) -> &'static str {
+    ["周一", "周二", "周三", "周四", "周五", "周六", "周日"][date.weekday().num_days_from_monday() as usize]
+}
+
+/// Weekday number of date, 1 (Monday) ~ 7 (Sunday), see bjtc_week_of_year
+#[inline]
+pub fn bjtc_weekday_num(date: &NaiveDate) -> u32 {
+    date.weekday().number_from_monday()
+}
+
+//
+
+/// Move n working days (Monday ~ Friday) forward, or backward if n < 0, saturating at NaiveDate::MIN / MAX
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// let friday = NaiveDate::from_ymd_opt(2024, 1, 19).unwrap();
+/// let monday = NaiveDate::from_ymd_opt(2024, 1, 22).unwrap();
+/// assert_eq!(bjtc_add_working_days(friday, 1), monday);
+/// assert_eq!(bjtc_add_working_days(monday, -1), friday);
+/// assert_eq!(bjtc_working_days_between(friday, monday), 1);
+/// assert_eq!(bjtc_working_days_between(monday, friday), -1);
+/// ```
+///
+pub fn bjtc_add_working_days(start: NaiveDate, n: i64) -> NaiveDate {
+    let before = working_days_before(&start);
+    let index = match n {
+        0 => return start,
+        n if n > 0 => before + i64::from(start.weekday().number_from_monday() <= 5) - 1,
+        _ => before,
+    }
+    .saturating_add(n);
+
+    // 第 index 个工作日, 0 是 0001-01-01 (周一)
+    index
+        .div_euclid(5)
+        .checked_mul(7)
+        .and_then(|days| i32::try_from(days.saturating_add(index.rem_euclid(5) + 1)).ok())
+        .and_then(NaiveDate::from_num_days_from_ce_opt)
+        .unwrap_or(if n > 0 { NaiveDate::MAX } else { NaiveDate::MIN })
+}
+
+/// Number of working days (Monday ~ Friday) in [start, end_exclusive), negative if end_exclusive < start
+#[inline]
+pub fn bjtc_working_days_between(start: NaiveDate, end_exclusive: NaiveDate) -> i64 {
+    working_days_before(&end_exclusive) - working_days_before(&start)
+}
+
+/// 0001-01-01 (周一) 至 date 之前的工作日数量
+fn working_days_before(date: &NaiveDate) -> i64 {
+    let days = date.num_days_from_ce() as i64 - 1;
+    days.div_euclid(7) * 5 + days.rem_euclid(7).min(5)
+}
+
+//
+
+/// Precision of timestamps used by bjtc_from_duration / bjtc_to_duration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimePrecision {
+    Millis,
+    Micros,
+}
+
+impl TimePrecision {
+    /// 每秒的单位数
+    fn per_second(&self) -> i64 {
+        match self {
+            Self::Millis => 1000,
+            Self::Micros => 1000000,
+        }
+    }
+}
+
+/// Convert duration to timestamp, accurate to milliseconds or microseconds
 ///
 /// At the same time, record datetime as anchor, create time::instant as now,
 /// then at any time after,
@@ -401,57 +867,220 @@ pub fn bjtc_tt(time: &DateTime<FixedOffset>) -> DateTime<FixedOffset> {
 /// let now = time::Instant::now();
 ///
 /// thread::sleep(time::Duration::from_millis(700));  // 0.7 seconds
-/// let t1 = bjtc_from_duration(&anchor, now.elapsed().as_secs_f64() * 1000.0);
-/// let t2 = bjtc_to_duration(&anchor, t1).unwrap();
+/// let t1 = bjtc_from_duration(&anchor, now.elapsed().as_secs_f64() * 1000.0, TimePrecision::Millis);
+/// let t2 = bjtc_to_duration(&anchor, t1, TimePrecision::Millis).unwrap();
 /// let diff = t2.as_secs_f64() - 0.7;
 /// assert!(diff > -0.5 && diff < 0.5);
 ///
-/// let t1 = bjtc_from_duration(&anchor, 1000.0);
-/// let t3 = bjtc_to_duration(&anchor, t1 - 1000);
+/// let t1 = bjtc_from_duration(&anchor, 1000.0, TimePrecision::Millis);
+/// let t3 = bjtc_to_duration(&anchor, t1 - 1000, TimePrecision::Millis);
 /// assert_eq!(t3.is_err(), false);
 /// assert_eq!(t3.unwrap().as_secs_f64(), 0.0);
-/// let t4 = bjtc_to_duration(&anchor, t1 - 1001);
+/// let t4 = bjtc_to_duration(&anchor, t1 - 1001, TimePrecision::Millis);
 /// assert_eq!(t4.is_err(), true);
 ///
-/// let diff = bjtc_to_duration(&anchor, bj_timestamp_millis())
+/// let diff = bjtc_to_duration(&anchor, bj_timestamp_micros(), TimePrecision::Micros)
 ///   .unwrap()
 ///   .as_secs_f64()
 ///   - 0.7;
 /// assert!(diff > -0.5 && diff < 0.5);
 /// ```
 ///
-pub fn bjtc_from_duration(anchor: &DateTime<Utc>, millis: f64) -> i64 {
-    match chrono::Duration::try_milliseconds(millis as i64) {
-        Some(dur) => (*anchor + dur).timestamp_millis(),
-        None => anchor.timestamp_micros(),
+pub fn bjtc_from_duration(anchor: &DateTime<Utc>, elapsed: f64, precision: TimePrecision) -> i64 {
+    let dur = match precision {
+        TimePrecision::Millis => chrono::Duration::try_milliseconds(elapsed as i64),
+        TimePrecision::Micros => Some(chrono::Duration::microseconds(elapsed as i64)),
+    };
+    let time = dur.and_then(|dur| anchor.checked_add_signed(dur)).unwrap_or(*anchor);
+
+    match precision {
+        TimePrecision::Millis => time.timestamp_millis(),
+        TimePrecision::Micros => time.timestamp_micros(),
     }
 }
 
 /// Convert timestamp to duration, see bjtc_from_duration
 #[auto_func_name]
-pub fn bjtc_to_duration(anchor: &DateTime<Utc>, timestamp_millis: i64) -> Result<time::Duration, MoreError> {
-    let elapsed = bjtc_nt(timestamp_millis / 1000, (timestamp_millis % 1000) as u32)
-        .m(m!(fname, &format!("timestamp={}", timestamp_millis)))?
+pub fn bjtc_to_duration(
+    anchor: &DateTime<Utc>,
+    timestamp: i64,
+    precision: TimePrecision,
+) -> Result<time::Duration, MoreError> {
+    let per_second = precision.per_second();
+    let elapsed = bjtc_nt_micros(
+        timestamp.div_euclid(per_second),
+        (timestamp.rem_euclid(per_second) * (1000000 / per_second)) as u32,
+    )
+    .m(m!(fname, &format!("timestamp={}", timestamp)))?
         - anchor.with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap());
 
-    if elapsed.num_milliseconds() >= 0 {
-        Ok(time::Duration::from_millis(elapsed.num_milliseconds() as u64))
-    } else {
-        m!(
+    let (count, from): (Option<i64>, fn(u64) -> time::Duration) = match precision {
+        TimePrecision::Millis => (Some(elapsed.num_milliseconds()), time::Duration::from_millis),
+        TimePrecision::Micros => (elapsed.num_microseconds(), time::Duration::from_micros),
+    };
+
+    match count {
+        Some(count) if count >= 0 => Ok(from(count as u64)),
+        _ => m!(
             fname,
-            &format!("{} 结果为负值 {}", timestamp_millis, elapsed.num_seconds()),
+            &format!("{} 结果为负值 {}", timestamp, elapsed.num_seconds()),
             "result"
-        )
+        ),
     }
 }
 
+/// Human readable duration, from the largest non-zero unit down to seconds, like "2h 15m 30s", "45m 0s", "3s"
+///
+/// Less than 1 second is shown as milliseconds, like "250ms", negative values are prefixed with "-".
+///
+/// Every unit below the largest is kept, even if zero, so 3661 s is "1h 1m 1s" and 3600 s is "1h 0m 0s",
+/// not trimmed to the two largest non-zero units.
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+///
+/// assert_eq!(bjtc_human_duration(8130.0), "2h 15m 30s");
+/// assert_eq!(bjtc_human_duration(2700.0), "45m 0s");
+/// assert_eq!(bjtc_human_duration(0.25), "250ms");
+/// assert_eq!(bjtc_human_duration(-3.0), "-3s");
+/// ```
+///
+pub fn bjtc_human_duration(seconds: f64) -> String {
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    let seconds = seconds.abs();
+
+    if seconds < 1.0 {
+        return format!("{}{}ms", sign, (seconds * 1000.0) as u64);
+    }
+
+    let total = seconds as u64;
+    let (h, m, s) = (total / 3600, total / 60 % 60, total % 60);
+    if h > 0 {
+        format!("{}{}h {}m {}s", sign, h, m, s)
+    } else if m > 0 {
+        format!("{}{}m {}s", sign, m, s)
+    } else {
+        format!("{}{}s", sign, s)
+    }
+}
+
+/// Each day in [start, end_exclusive), empty if start >= end_exclusive
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// let start = NaiveDate::from_ymd_opt(2024, 2, 28).unwrap();
+/// let end = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+/// assert_eq!(bjtc_date_range(start, end).collect::<Vec<_>>(), [start, start.succ_opt().unwrap()]);
+/// assert_eq!(bjtc_date_range_inclusive(start, end).last(), Some(end));
+/// ```
+///
+pub fn bjtc_date_range(start: NaiveDate, end_exclusive: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    iter::successors(Some(start), NaiveDate::succ_opt).take_while(move |date| *date < end_exclusive)
+}
+
+/// Each day in [start, end], see bjtc_date_range
+pub fn bjtc_date_range_inclusive(start: NaiveDate, end: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    iter::successors(Some(start), NaiveDate::succ_opt).take_while(move |date| *date <= end)
+}
+
+/// Each day in [start, end] with its Beijing time timestamp range [day_start, day_end)
+///
+/// day_end is the timestamp of the next day's 00:00:00, suitable for `ts >= day_start AND ts < day_end`
+///
+/// ## Usage
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use python_comm::use_basic::*;
+///
+/// let start = NaiveDate::from_ymd_opt(2024, 2, 28).unwrap();
+/// let end = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+/// let days = bjtc_timestamp_range(&start, &end).collect::<Vec<_>>();
+/// assert_eq!(days.len(), 3);
+/// assert_eq!(days[0], (start, bjtc_dn(&start), bjtc_dn(&start) + 86400));
+/// assert_eq!(days[2].0, end);
+/// ```
+///
+pub fn bjtc_timestamp_range(start: &NaiveDate, end: &NaiveDate) -> impl Iterator<Item = (NaiveDate, i64, i64)> {
+    bjtc_date_range_inclusive(*start, *end).map(|date| (date, bjtc_dn(&date), bjtc_dn(&date) + 86400))
+}
+
+//
+
+/// Parse a JSON string value in the format of bjtc_ts
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+/// use serde_json::json;
+///
+/// let t = bj_time_init(2024, 1, 15, 9, 0, 0);
+/// assert_eq!(bjtc_from_json(&json!("2024-01-15T09:00:00+08:00")).unwrap(), t);
+/// assert!(bjtc_from_json(&json!(1705280400)).is_err());
+/// ```
+///
+#[cfg(feature = "use_json")]
+#[auto_func_name]
+pub fn bjtc_from_json(value: &serde_json::Value) -> Result<DateTime<FixedOffset>, MoreError> {
+    match value.as_str() {
+        Some(text) => bjtc_st(text).m(m!(fname)),
+        None => m!(fname, format!("not a string: {}", value), "result"),
+    }
+}
+
+/// Convert time to a JSON string value in the format of bjtc_ts
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+/// use serde_json::json;
+///
+/// let t = bj_time_init(2024, 1, 15, 9, 0, 0);
+/// assert_eq!(bjtc_to_json(&t), json!("2024-01-15T09:00:00+08:00"));
+/// ```
+///
+#[cfg(feature = "use_json")]
+#[inline]
+pub fn bjtc_to_json(time: &DateTime<FixedOffset>) -> serde_json::Value {
+    serde_json::Value::String(bjtc_ts(time))
+}
+
 #[cfg(test)]
 mod test {
-    use chrono::Duration;
+    use chrono::{Duration, Weekday};
 
     use super::*;
     use std::thread;
 
+    #[test]
+    fn test_bj_time_from() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(bj_time_from_date(date, 0, 0, 0), bj_time_init(2024, 2, 29, 0, 0, 0));
+        assert_eq!(
+            bj_time_from_date(date, 23, 59, 59),
+            bj_time_init(2024, 2, 29, 23, 59, 59)
+        );
+        assert_eq!(bj_time_from_date(date, 0, 0, 0), bjtc_dt(&date));
+
+        let time = bj_time_init(2024, 2, 29, 23, 59, 59);
+        let text = bjtc_tn(&time).to_string();
+        assert_eq!(bj_time_from_timestamp_str(&text).unwrap(), time);
+        assert_eq!(
+            bj_time_from_timestamp_str(" 0 ").unwrap(),
+            bj_time_init(1970, 1, 1, 8, 0, 0)
+        );
+        assert!(bj_time_from_timestamp_str("").is_err());
+        assert!(bj_time_from_timestamp_str("1.5").is_err());
+    }
+
     #[test]
     fn test_bjtc() {
         println!("date  {}", bj_date());
@@ -507,6 +1136,150 @@ mod test {
         assert_eq!(bjtc_tt(&e129), t129);
     }
 
+    #[test]
+    fn test_bjtc_add_sub() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        // 跨月、跨年
+        assert_eq!(bjtc_add_days(&date(2024, 2, 28), 1), date(2024, 2, 29));
+        assert_eq!(bjtc_add_days(&date(2023, 2, 28), 1), date(2023, 3, 1));
+        assert_eq!(bjtc_add_days(&date(2023, 12, 31), 1), date(2024, 1, 1));
+        assert_eq!(bjtc_sub_days(&date(2024, 1, 1), 1), date(2023, 12, 31));
+        assert_eq!(bjtc_add_days(&date(2024, 1, 1), -1), date(2023, 12, 31));
+
+        // 越界
+        assert_eq!(bjtc_sub_days(&NaiveDate::MIN, 1), NaiveDate::MIN);
+        assert_eq!(bjtc_sub_days(&date(2024, 1, 1), i64::MAX), NaiveDate::MIN);
+        assert_eq!(bjtc_sub_days(&date(2024, 1, 1), i64::MIN), NaiveDate::MAX);
+        assert_eq!(bjtc_add_days(&NaiveDate::MAX, 1), NaiveDate::MAX);
+
+        let time = bj_time_init(2023, 12, 31, 23, 30, 0);
+        assert_eq!(bjtc_add_hours(&time, 1), bj_time_init(2024, 1, 1, 0, 30, 0));
+        assert_eq!(bjtc_sub_hours(&time, 24), bj_time_init(2023, 12, 30, 23, 30, 0));
+        assert_eq!(bjtc_add_hours(&time, 1).offset(), time.offset());
+        assert_eq!(
+            bjtc_sub_hours(&time, i64::MAX).naive_utc(),
+            DateTime::<Utc>::MIN_UTC.naive_utc()
+        );
+        assert_eq!(
+            bjtc_add_hours(&time, i64::MAX).naive_utc(),
+            DateTime::<Utc>::MAX_UTC.naive_utc()
+        );
+    }
+
+    #[test]
+    fn test_bjtc_add_working_days() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        // 与逐日计算的结果一致
+        let start = date(2023, 12, 1);
+        for offset in 0..70 {
+            let from = bjtc_add_days(&start, offset);
+            for to in bjtc_date_range(from, date(2024, 2, 10)) {
+                let count = bjtc_date_range(from, to)
+                    .filter(|day| bjtc_weekday_num(day) <= 5)
+                    .count() as i64;
+                assert_eq!(bjtc_working_days_between(from, to), count);
+                assert_eq!(bjtc_working_days_between(to, from), -count);
+            }
+        }
+
+        // 跨月、跨年
+        assert_eq!(bjtc_working_days_between(date(2024, 1, 1), date(2024, 2, 1)), 23);
+        assert_eq!(bjtc_working_days_between(date(2023, 12, 29), date(2024, 1, 2)), 2);
+        assert_eq!(bjtc_add_working_days(date(2023, 12, 29), 1), date(2024, 1, 1));
+        assert_eq!(bjtc_add_working_days(date(2024, 1, 31), 2), date(2024, 2, 2));
+        assert_eq!(bjtc_add_working_days(date(2024, 1, 31), 5), date(2024, 2, 7));
+
+        // 周末出发, 负数
+        assert_eq!(bjtc_add_working_days(date(2024, 1, 20), 1), date(2024, 1, 22));
+        assert_eq!(bjtc_add_working_days(date(2024, 1, 21), -1), date(2024, 1, 19));
+        assert_eq!(bjtc_add_working_days(date(2024, 1, 1), -1), date(2023, 12, 29));
+        assert_eq!(bjtc_add_working_days(date(2024, 1, 22), -6), date(2024, 1, 12));
+        assert_eq!(bjtc_add_working_days(date(2024, 1, 20), 0), date(2024, 1, 20));
+
+        for n in -30..30 {
+            let start = date(2024, 1, 17);
+            let end = bjtc_add_working_days(start, n);
+            assert!(bjtc_weekday_num(&end) <= 5);
+            assert_eq!(bjtc_working_days_between(start, end), n, "{}", n);
+        }
+
+        assert_eq!(bjtc_add_working_days(date(2024, 1, 1), i64::MAX), NaiveDate::MAX);
+        assert_eq!(bjtc_add_working_days(date(2024, 1, 1), i64::MIN / 2), NaiveDate::MIN);
+    }
+
+    #[test]
+    fn test_bjtc_date_range() {
+        let start = NaiveDate::from_ymd_opt(2023, 12, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let days = (end - start).num_days() as usize;
+
+        assert_eq!(bjtc_date_range(start, end).count(), days);
+        assert_eq!(bjtc_date_range_inclusive(start, end).count(), days + 1);
+        assert_eq!(bjtc_date_range(start, end).last(), end.pred_opt());
+        assert_eq!(bjtc_date_range_inclusive(start, end).last(), Some(end));
+
+        assert_eq!(bjtc_date_range(start, start).count(), 0);
+        assert_eq!(bjtc_date_range(end, start).count(), 0);
+        assert_eq!(bjtc_date_range_inclusive(start, start).count(), 1);
+        assert_eq!(bjtc_date_range_inclusive(end, start).count(), 0);
+        assert_eq!(bjtc_date_range_inclusive(NaiveDate::MAX, NaiveDate::MAX).count(), 1);
+    }
+
+    #[test]
+    fn test_bjtc_days_in_month() {
+        let days = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+        for month in 1..=12 {
+            assert_eq!(bjtc_days_in_month(2023, month), days[month as usize - 1]);
+            assert_eq!(
+                bjtc_last_day_of_month(2023, month),
+                NaiveDate::from_ymd_opt(2023, month, days[month as usize - 1]).unwrap()
+            );
+        }
+        assert_eq!(bjtc_days_in_month(2023, 0), 0);
+        assert_eq!(bjtc_days_in_month(2023, 13), 0);
+
+        // 闰年
+        assert_eq!(bjtc_days_in_month(2024, 2), 29);
+        assert_eq!(bjtc_days_in_month(2000, 2), 29);
+        assert_eq!(bjtc_days_in_month(1900, 2), 28);
+        assert_eq!(
+            bjtc_last_day_of_month(2024, 2),
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+        assert_eq!(
+            bjtc_last_day_of_month(2100, 2),
+            NaiveDate::from_ymd_opt(2100, 2, 28).unwrap()
+        );
+        for year in 1890..2110 {
+            assert_eq!(bjtc_is_leap_year(year), NaiveDate::from_ymd_opt(year, 2, 29).is_some());
+        }
+    }
+
+    #[test]
+    fn test_bjtc_format_parse() {
+        let time = bj_time_init(2024, 1, 15, 9, 30, 5) + Duration::milliseconds(123);
+
+        for fmt in [
+            "%Y-%m-%dT%H:%M:%S%.3f%:z",
+            "%Y-%m-%d %H:%M:%S%.f %z",
+            "%Y%m%d%H%M%S%3f%z",
+            "%d/%m/%Y %H:%M:%S.%3f %:z",
+        ] {
+            let text = bjtc_format(&time, fmt);
+            assert_eq!(bjtc_parse(&text, fmt).unwrap(), time, "{}", fmt);
+        }
+        assert_eq!(bjtc_format(&time, "%Y-%m-%d %H:%M:%S%.3f"), "2024-01-15 09:30:05.123");
+
+        // 不含时区、格式不符
+        assert!(bjtc_parse("2024-01-15 09:30:05", "%Y-%m-%d %H:%M:%S").is_err());
+        assert!(bjtc_parse("2024-01-15", "%Y/%m/%d %z").is_err());
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        assert_eq!(bjtc_format_date(&date, "%Y年%-m月%-d日"), "2024年1月5日");
+    }
+
     #[test]
     fn test_bjtc_from_to_duration() {
         let anchor = Utc::now();
@@ -514,19 +1287,231 @@ mod test {
 
         thread::sleep(time::Duration::from_millis(700)); // 0.7 秒
         let elapsed = now.elapsed().as_secs_f64();
-        let t1 = bjtc_from_duration(&anchor, elapsed * 1000.0);
-        let t2 = bjtc_to_duration(&anchor, t1).unwrap();
+        let t1 = bjtc_from_duration(&anchor, elapsed * 1000.0, TimePrecision::Millis);
+        let t2 = bjtc_to_duration(&anchor, t1, TimePrecision::Millis).unwrap();
         let diff = t2.as_secs_f64() - elapsed;
         assert!(diff > -0.01 && diff < 0.01);
 
-        let t1 = bjtc_from_duration(&anchor, 1000.0);
-        let t3 = bjtc_to_duration(&anchor, t1 - 1000);
+        let t1 = bjtc_from_duration(&anchor, 1000.0, TimePrecision::Millis);
+        let t3 = bjtc_to_duration(&anchor, t1 - 1000, TimePrecision::Millis);
         assert_eq!(t3.is_err(), false);
         assert_eq!(t3.unwrap().as_secs_f64(), 0.0);
-        let t4 = bjtc_to_duration(&anchor, t1 - 1001);
+        let t4 = bjtc_to_duration(&anchor, t1 - 1001, TimePrecision::Millis);
         assert_eq!(t4.is_err(), true);
 
-        let diff = bjtc_to_duration(&anchor, bj_timestamp_millis()).unwrap().as_secs_f64() - 0.7;
+        let diff = bjtc_to_duration(&anchor, bj_timestamp_millis(), TimePrecision::Millis)
+            .unwrap()
+            .as_secs_f64()
+            - 0.7;
         assert!(diff > -0.1 && diff < 0.1);
+
+        // 微秒
+        let t1 = bjtc_from_duration(&anchor, elapsed * 1000000.0, TimePrecision::Micros);
+        let t2 = bjtc_to_duration(&anchor, t1, TimePrecision::Micros).unwrap();
+        let diff = t2.as_secs_f64() - elapsed;
+        assert!(diff > -0.000002 && diff < 0.000002);
+
+        let t1 = bjtc_from_duration(&anchor, 1000.0, TimePrecision::Micros);
+        assert_eq!(
+            bjtc_to_duration(&anchor, t1 - 1000, TimePrecision::Micros)
+                .unwrap()
+                .as_micros(),
+            0
+        );
+        assert!(bjtc_to_duration(&anchor, t1 - 1001, TimePrecision::Micros).is_err());
+    }
+
+    #[test]
+    fn test_bjtc_from_ymd() {
+        assert_eq!(bjtc_from_ymd(2024, 1, 15).unwrap(), bj_time_init(2024, 1, 15, 0, 0, 0));
+        assert_eq!(bjtc_from_ymd(2024, 1, 15).unwrap().offset().local_minus_utc(), 8 * 3600);
+        assert_eq!(
+            bjtc_from_ymd_hms(2024, 12, 31, 23, 59, 59).unwrap(),
+            bj_time_init(2024, 12, 31, 23, 59, 59)
+        );
+
+        assert!(bjtc_from_ymd(2024, 13, 1).is_err());
+        assert!(bjtc_from_ymd(2024, 4, 31).is_err());
+        assert!(bjtc_from_ymd_hms(2024, 1, 15, 24, 0, 0).is_err());
+        assert!(bjtc_from_ymd_hms(2024, 1, 15, 9, 60, 0).is_err());
+    }
+
+    #[test]
+    fn test_bjtc_human_duration() {
+        assert_eq!(bjtc_human_duration(3600.0), "1h 0m 0s");
+        assert_eq!(bjtc_human_duration(3661.0), "1h 1m 1s");
+        assert_eq!(bjtc_human_duration(3599.9), "59m 59s");
+        assert_eq!(bjtc_human_duration(60.0), "1m 0s");
+        assert_eq!(bjtc_human_duration(59.0), "59s");
+        assert_eq!(bjtc_human_duration(1.0), "1s");
+        assert_eq!(bjtc_human_duration(0.5), "500ms");
+        assert_eq!(bjtc_human_duration(0.0), "0ms");
+        assert_eq!(bjtc_human_duration(-100.0), "-1m 40s");
+        assert_eq!(bjtc_human_duration(-0.25), "-250ms");
+    }
+
+    #[cfg(feature = "use_json")]
+    #[test]
+    fn test_bjtc_json() {
+        let t = bj_time_init(2024, 1, 15, 9, 0, 0);
+        let value = bjtc_to_json(&t);
+        assert_eq!(
+            value,
+            serde_json::Value::String("2024-01-15T09:00:00+08:00".to_string())
+        );
+        assert_eq!(bjtc_from_json(&value).unwrap(), t);
+
+        // 其它时区, 转为 +8 时区
+        let value = serde_json::json!("2024-01-15T01:00:00+00:00");
+        assert_eq!(bjtc_from_json(&value).unwrap(), t);
+        assert_eq!(bjtc_to_json(&bjtc_from_json(&value).unwrap()), bjtc_to_json(&t));
+
+        assert!(bjtc_from_json(&serde_json::json!("2024-01-15")).is_err());
+        assert!(bjtc_from_json(&serde_json::json!(null)).is_err());
+        assert!(bjtc_from_json(&serde_json::json!(1705280400)).is_err());
+    }
+
+    #[test]
+    fn test_bjtc_parse_flexible() {
+        let formats = ["%Y/%m/%d %H:%M %z", "%d-%m-%Y %H:%M:%S %z", "%Y.%m.%d %H%M%S%:z"];
+        let time = bj_time_init(2024, 1, 15, 9, 30, 0);
+        assert_eq!(bjtc_parse_flexible("2024/01/15 09:30 +0800", &formats).unwrap(), time);
+        assert_eq!(
+            bjtc_parse_flexible("15-01-2024 09:30:00 +0800", &formats).unwrap(),
+            time
+        );
+        assert_eq!(bjtc_parse_flexible("2024.01.15 093000+08:00", &formats).unwrap(), time);
+
+        let err = bjtc_parse_flexible("2024-01-15 09:30", &formats)
+            .unwrap_err()
+            .to_string();
+        for fmt in formats {
+            assert!(err.contains(fmt));
+        }
+        assert!(bjtc_parse_flexible("2024/01/15 09:30 +0800", &[]).is_err());
+
+        let formats = ["%Y/%m/%d", "%d-%m-%Y", "%Y.%m.%d"];
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        assert_eq!(bjtc_parse_date_flexible("2024/01/15", &formats).unwrap(), date);
+        assert_eq!(bjtc_parse_date_flexible("15-01-2024", &formats).unwrap(), date);
+        assert_eq!(bjtc_parse_date_flexible("2024.01.15", &formats).unwrap(), date);
+        assert!(bjtc_parse_date_flexible("01/15/2024", &formats).is_err());
+    }
+
+    #[test]
+    fn test_bjtc_quarter() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        for month in 1..=12 {
+            let expected = [1, 1, 1, 2, 2, 2, 3, 3, 3, 4, 4, 4][month as usize - 1];
+            assert_eq!(bjtc_quarter(&date(2023, month, 15)), expected);
+        }
+
+        let quarters = [
+            (date(2024, 1, 1), date(2024, 3, 31)),
+            (date(2024, 4, 1), date(2024, 6, 30)),
+            (date(2024, 7, 1), date(2024, 9, 30)),
+            (date(2024, 10, 1), date(2024, 12, 31)),
+        ];
+        for (start, end) in quarters {
+            for day in [start, bjtc_add_days(&start, 40), end] {
+                assert_eq!(bjtc_quarter_start(&day), start);
+                assert_eq!(bjtc_quarter_end(&day), end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bjtc_st_assume_bj() {
+        let t = bj_time_init(2024, 1, 15, 9, 0, 0);
+
+        assert!(bjtc_st("2024-01-15T09:00:00").is_err());
+        assert_eq!(bjtc_st_assume_bj("2024-01-15T09:00:00").unwrap(), t);
+        assert_eq!(bjtc_st_assume_bj("2024-01-15T09:00:00+08:00").unwrap(), t);
+        assert_eq!(bjtc_st_assume_bj("2024-01-15T10:00:00+09:00").unwrap(), t);
+        assert!(bjtc_st_assume_bj("2024-01-15 09:00").is_err());
+    }
+
+    #[test]
+    fn test_bjtc_st_naive() {
+        for text in ["2024-01-15 09:30:00", "1970-01-01 08:00:00", "2024-02-29 23:59:59"] {
+            assert_eq!(bjtc_ts_naive(&bjtc_st_naive(text).unwrap()), text);
+        }
+
+        // 其它时区的时间按北京时间输出
+        let t = bjtc_st("2024-01-15T10:30:00+09:00").unwrap();
+        assert_eq!(bjtc_ts_naive(&t), "2024-01-15 09:30:00");
+        assert_eq!(bjtc_st_naive(&bjtc_ts_naive(&t)).unwrap(), t);
+
+        assert!(bjtc_st_naive("2024-01-15 09:30").is_err());
+        assert!(bjtc_st_naive("2024-01-15 09:30:00+08:00").is_err());
+        assert!(bjtc_st_naive("2023-02-29 09:30:00").is_err());
+    }
+
+    #[test]
+    fn test_bjtc_timestamp_range() {
+        let start = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+
+        let days = bjtc_timestamp_range(&start, &end).collect::<Vec<_>>();
+        assert_eq!(days.len(), 3);
+        for (i, (date, day_start, day_end)) in days.iter().enumerate() {
+            assert_eq!(*date, start + Duration::days(i as i64));
+            assert_eq!(bjtc_nb(*day_start, 0).unwrap(), format!("{}T00:00:00", bjtc_ds(date)));
+            assert_eq!(*day_end - *day_start, 86400);
+        }
+        assert_eq!(days[0].2, days[1].1);
+
+        assert_eq!(bjtc_timestamp_range(&start, &start).count(), 1);
+        assert_eq!(bjtc_timestamp_range(&end, &start).count(), 0);
+    }
+
+    #[test]
+    fn test_bjtc_week() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(bjtc_week_of_year(&date(2024, 1, 1)), 1);
+        assert_eq!(bjtc_weekday_num(&date(2024, 1, 1)), 1);
+        assert_eq!(bjtc_weekday_cn(&date(2024, 1, 1)), "周一");
+
+        // 2021-01-03 周日, 属于 2020 年第 53 周
+        assert_eq!(bjtc_week_of_year(&date(2021, 1, 3)), 53);
+        assert_eq!(bjtc_weekday_num(&date(2021, 1, 3)), 7);
+        assert_eq!(bjtc_weekday_cn(&date(2021, 1, 3)), "周日");
+
+        // 2024-12-30 周一, 属于 2025 年第 1 周
+        assert_eq!(bjtc_week_of_year(&date(2024, 12, 30)), 1);
+        assert_eq!(bjtc_weekday_cn(&date(2024, 12, 28)), "周六");
+        assert_eq!(bjtc_weekday_num(&date(2024, 12, 28)), 6);
+
+        // 周一 ~ 周日, 含跨年
+        assert_eq!(
+            bjtc_week_range(&date(2024, 1, 17)),
+            (date(2024, 1, 15), date(2024, 1, 21))
+        );
+        assert_eq!(
+            bjtc_week_range(&date(2024, 1, 15)),
+            (date(2024, 1, 15), date(2024, 1, 21))
+        );
+        assert_eq!(
+            bjtc_week_range(&date(2024, 1, 21)),
+            (date(2024, 1, 15), date(2024, 1, 21))
+        );
+        assert_eq!(
+            bjtc_week_range(&date(2021, 1, 3)),
+            (date(2020, 12, 28), date(2021, 1, 3))
+        );
+        assert_eq!(
+            bjtc_week_range(&date(2024, 12, 31)),
+            (date(2024, 12, 30), date(2025, 1, 5))
+        );
+
+        // chrono 范围的第一周 / 最后一周不完整
+        let (monday, sunday) = bjtc_week_range(&NaiveDate::MIN);
+        assert_eq!(monday, NaiveDate::MIN);
+        assert_eq!(sunday.weekday(), Weekday::Sun);
+        let (monday, sunday) = bjtc_week_range(&NaiveDate::MAX);
+        assert_eq!(monday.weekday(), Weekday::Mon);
+        assert_eq!(sunday, NaiveDate::MAX);
     }
 }
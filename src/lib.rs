@@ -16,6 +16,9 @@ mod tokio_helper;
 
 pub mod textsearcher;
 
+#[doc(hidden)]
+pub use macros::{ok_or_log_message, ok_or_return_log_message, some_or_return_log_message, timed_message};
+
 /// common basic functions.
 ///
 /// # Usage
@@ -29,17 +32,28 @@ pub mod use_basic {
         crate::{
             crate_version,
             datetime::{
-                bj_date, bj_dates, bj_time, bj_time_init, bj_timeb, bj_times, bj_timestamp, bj_timestamp_millis,
-                bjtc_bd, bjtc_bf, bjtc_bn, bjtc_bs, bjtc_bt, bjtc_df, bjtc_dn, bjtc_ds, bjtc_dt, bjtc_fb, bjtc_fd,
-                bjtc_from_duration, bjtc_fs, bjtc_ft, bjtc_nb, bjtc_nd, bjtc_ns, bjtc_nt, bjtc_sb, bjtc_sd, bjtc_sf,
-                bjtc_sn, bjtc_st, bjtc_tb, bjtc_td, bjtc_tf, bjtc_tn, bjtc_to_duration, bjtc_ts, bjtc_tt,
+                bj_date, bj_dates, bj_now_ms, bj_time, bj_time_from_date, bj_time_from_timestamp_str, bj_time_init,
+                bj_timeb, bj_times, bj_timestamp, bj_timestamp_micros, bj_timestamp_millis, bjtc_add_days,
+                bjtc_add_hours, bjtc_add_working_days, bjtc_bd, bjtc_bf, bjtc_bn, bjtc_bs, bjtc_bt, bjtc_date_range,
+                bjtc_date_range_inclusive, bjtc_days_in_month, bjtc_df, bjtc_dn, bjtc_ds, bjtc_dt, bjtc_fb, bjtc_fd,
+                bjtc_format, bjtc_format_date, bjtc_from_duration, bjtc_from_ymd, bjtc_from_ymd_hms, bjtc_fs, bjtc_ft,
+                bjtc_human_duration, bjtc_is_leap_year, bjtc_last_day_of_month, bjtc_nb, bjtc_nd, bjtc_ns, bjtc_nt,
+                bjtc_nt_micros, bjtc_parse, bjtc_parse_date_flexible, bjtc_parse_flexible, bjtc_quarter,
+                bjtc_quarter_end, bjtc_quarter_start, bjtc_sb, bjtc_sd, bjtc_sf, bjtc_sn, bjtc_st, bjtc_st_assume_bj,
+                bjtc_st_naive, bjtc_sub_days, bjtc_sub_hours, bjtc_tb, bjtc_td, bjtc_tf, bjtc_timestamp_range, bjtc_tn,
+                bjtc_to_duration, bjtc_ts, bjtc_ts_naive, bjtc_tt, bjtc_week_of_year, bjtc_week_range, bjtc_weekday_cn,
+                bjtc_weekday_num, bjtc_working_days_between, TimePrecision,
             },
             ok_or_return, some_or_return,
-            textsearcher::TextSearcher,
+            textsearcher::{NormalizeMode, TextSearcher, TextSearcherForSerde},
+            timed,
         },
         python_comm_macros::build_time,
         rust_decimal::{prelude::FromPrimitive, Decimal},
     };
+
+    #[cfg(feature = "use_json")]
+    pub use crate::datetime::{bjtc_from_json, bjtc_to_json};
 }
 
 /// ## Usage
@@ -62,11 +76,52 @@ pub mod use_basic {
 /// assert_eq!((1,1.1,-1.1,true,false).to_limit_str3(4, 4, 12), "(0 1,1.1,-1.1,true,false 0)");
 /// assert_eq!((1, "1", 1, vec![vec![1]], vec![1]).to_limit_str3(4, 4, 12), "(0 1,1,1,[1 [2 1 2] 1],[3 1 3] 0)");
 /// assert_eq!(Abc{a:1, b:"1", c:1, d:vec![vec![1]], e:vec![1]}.to_limit_str3(4, 4, 12), "Abc(0 a:1,b:1,c:1,d:[1 [2 1 2] 1],e:[3 1 3] 0)");
+///
+/// use python_comm::use_basic::Decimal;
+///
+/// #[derive(LimitPack)]
+/// struct Price {
+///     amount: Decimal,
+/// }
+///
+/// assert_eq!(Price{amount: Decimal::new(12345, 2)}.to_limit_str3(4, 4, 4), "Price(0 amount:123.45 0)");
+/// ```
+///
+/// ## Enum
+///
+/// `#[derive(LimitPack)]` only handles structs with named fields, enums implement `LimitPackAble` by hand,
+/// rendering the variant fields as tuple / `ForStruct` so that the limits still apply.
+///
+/// ```
+/// use python_comm::use_limit_pack::*;
+///
+/// enum Shape {
+///     Empty,
+///     Circle(i32),
+///     Rect { w: i32, h: Vec<i32> },
+/// }
+///
+/// impl LimitPackAble for Shape {
+///     fn to_limit_str(&self, limit: &mut Limit) -> String {
+///         match self {
+///             Shape::Empty => "Empty".to_string(),
+///             Shape::Circle(r) => format!("Circle{}", (r,).to_limit_str(limit)),
+///             Shape::Rect { w, h } => format!(
+///                 "Rect{}",
+///                 (ForStruct { k: "w".to_string(), v: w }, ForStruct { k: "h".to_string(), v: h }).to_limit_str(limit)
+///             ),
+///         }
+///     }
+/// }
+///
+/// assert_eq!(Shape::Empty.to_limit_str3(4, 4, 12), "Empty");
+/// assert_eq!(Shape::Circle(1).to_limit_str3(4, 4, 12), "Circle(0 1 0)");
+/// assert_eq!(Shape::Rect{w:1, h:vec![1,2,3,4,5]}.to_limit_str3(4, 4, 12), "Rect(0 w:1,h:[1 1,2,...1...4,5 1] 0)");
 /// ```
 ///
 pub mod use_limit_pack {
     pub use {
-        crate::limit_pack::{ForStruct, Limit, LimitPackAble},
+        crate::limit_pack::{ForStruct, Limit, LimitPackAble, MAX_TUPLE_LIMIT_PACK},
         python_comm_macros::LimitPack,
     };
 }
@@ -95,8 +150,9 @@ pub mod use_limit_pack {
 pub mod use_m {
     pub use {
         crate::{
-            m,
-            more_error::{AddMoreError, AsMoreError, LessError, MoreError},
+            ensure, ensure_ok, ensure_some, m, m_code,
+            more_error::{AddMoreError, AsMoreError, LessError, LogError, MoreError, MoreErrors, SourceMoreError},
+            ok_or_log, ok_or_return_log, raise_error, some_or_return_log,
         },
         python_comm_macros::auto_func_name,
     };
@@ -126,11 +182,12 @@ pub mod use_sql {
     pub use {
         crate::{
             sql_date::{SqlDate, SqlTime},
-            sql_op::{CreateDbPool, DbPool, DbPoolArgs, SqlModel},
+            sql_op::{CreateDbPool, DbPool, DbPoolArgs, GetConn, SqlModel},
         },
         mysql::{
             params,
             prelude::{ConvIr, FromValue},
+            Transaction,
         },
         python_comm_macros::AsSqlModel,
     };
@@ -138,5 +195,8 @@ pub mod use_sql {
 
 #[cfg(feature = "use_tokio")]
 pub mod use_tokio {
-    pub use crate::tokio_helper::{join_all, join_all_and_reduce, join_to_happy};
+    pub use crate::tokio_helper::{
+        bounded_join_all, join_all, join_all_and_reduce, join_all_results, join_all_timeout, join_all_with_progress,
+        join_first, join_first_ok, join_n_of_m, join_to_happy, with_retry,
+    };
 }
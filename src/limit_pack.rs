@@ -1,11 +1,31 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use {
+    crate::datetime::{bjtc_format, bjtc_ft},
+    rust_decimal::Decimal,
+    std::{
+        borrow::Cow,
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+        rc::Rc,
+        sync::{
+            atomic::{
+                AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32, AtomicU64,
+                AtomicU8, AtomicUsize, Ordering,
+            },
+            Arc,
+        },
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+};
 
 pub struct Limit {
     array_limit: usize,
     dict_limit: usize,
     str_limit: usize,
+    fixed_arrays_unlimited: bool,
+    show_some_wrapper: bool,
     pair_seq: u32,
     pair_stack: Vec<u32>,
+    truncation_marker: Option<String>,
 }
 
 impl Limit {
@@ -15,8 +35,19 @@ impl Limit {
             array_limit: self.array_limit,
             dict_limit: self.dict_limit,
             str_limit,
+            fixed_arrays_unlimited: self.fixed_arrays_unlimited,
+            show_some_wrapper: self.show_some_wrapper,
             pair_seq: self.pair_seq,
             pair_stack: Vec::new(),
+            truncation_marker: self.truncation_marker.clone(),
+        }
+    }
+
+    /// 省略标识, 缺省为 ...{skip}...
+    fn marker(&self, skip: usize) -> String {
+        match &self.truncation_marker {
+            Some(marker) => marker.clone(),
+            None => format!("...{}...", skip),
         }
     }
 
@@ -26,8 +57,11 @@ impl Limit {
             array_limit,
             dict_limit,
             str_limit,
+            fixed_arrays_unlimited: false,
+            show_some_wrapper: false,
             pair_seq: 0,
             pair_stack: Vec::new(),
+            truncation_marker: None,
         }
     }
 
@@ -55,7 +89,7 @@ impl Limit {
                 text += &format!("{}:{}{}", k_text, v_text, if i < data.len() - 1 { "," } else { "" });
             } else if i == self.dict_limit / 2 {
                 // 第一个 skip
-                text += &format!("...{}...", skip);
+                text += &self.marker(skip);
             } else {
                 // 其它 skip
             }
@@ -67,6 +101,42 @@ impl Limit {
         text
     }
 
+    /// 构造 dict 类型, 按 key 的字符串排序, 用于 HashMap 等无序类型, 保证输出稳定
+    pub fn new_dict_sorted<T1, T2>(&mut self, data: &[(T1, T2)]) -> String
+    where
+        T1: LimitPackAble,
+        T2: LimitPackAble,
+    {
+        // 排序用的 key 由独立的 Limit 完整生成, 不影响 pair_seq
+        let mut sorted = data
+            .iter()
+            .map(|(k, v)| (k.to_limit_str3(usize::MAX, usize::MAX, 0), k, v))
+            .collect::<Vec<_>>();
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+
+        self.new_dict(&sorted.into_iter().map(|(_, k, v)| (k, v)).collect())
+    }
+
+    /// 构造定长数组类型, 与 list 相同, 但不受 array_limit 限制
+    pub fn new_fixed_array<T>(&mut self, data: &[T]) -> String
+    where
+        T: LimitPackAble,
+    {
+        // 左标识
+        let pair_seq = self.pair_seq;
+        let mut text = format!("{}{} ", '[', pair_seq);
+        self.pair_seq += 1;
+
+        for (i, v) in data.iter().enumerate() {
+            let v_text = v.to_limit_str(self);
+            text += &format!("{}{}", v_text, if i < data.len() - 1 { "," } else { "" });
+        }
+
+        // 右标识
+        text += &format!("{}{}{}", if data.is_empty() { "" } else { " " }, pair_seq, ']');
+        text
+    }
+
     /// 构造 list 类型
     pub fn new_list<T>(&mut self, data: &Vec<T>) -> String
     where
@@ -90,7 +160,7 @@ impl Limit {
                 text += &format!("{}{}", v_text, if i < data.len() - 1 { "," } else { "" });
             } else if i == self.array_limit / 2 {
                 // 第一个 skip
-                text += &format!("...{}...", skip);
+                text += &self.marker(skip);
             } else {
                 // 其它 skip
             }
@@ -101,6 +171,49 @@ impl Limit {
         text
     }
 
+    /// 构造 struct 类型, fields 为 (字段名, 值), 输出与 new_struct 相同
+    ///
+    /// 内部完成 push_and_inc() / pop_start() / pop_end(), 各字段直接写入结果, 不需要另外分配 Vec
+    pub fn new_named_struct(&mut self, name: &str, fields: &[(&str, &dyn LimitPackAble)]) -> String {
+        // 各字段
+        self.push_and_inc();
+        let mut body = String::new();
+        for (i, (k, v)) in fields.iter().enumerate() {
+            body += &format!(
+                "{}:{}{}",
+                k,
+                v.to_limit_str(self),
+                if i < fields.len() - 1 { "," } else { "" }
+            );
+        }
+        let pair_seq = self.pop_start();
+
+        // 左右标识
+        let text = format!(
+            "{}{}{} {}{}{}{}",
+            name,
+            '(',
+            self.pair_seq,
+            body,
+            if fields.is_empty() { "" } else { " " },
+            self.pair_seq,
+            ')'
+        );
+        self.pop_end(pair_seq);
+        text
+    }
+
+    /// 构造 Option 类型, 输出 None 或 Some(...)
+    pub fn new_option<T>(&mut self, opt: &Option<T>) -> String
+    where
+        T: LimitPackAble,
+    {
+        match opt {
+            Some(obj) => format!("Some({})", obj.to_limit_str(self)),
+            None => "None".to_string(),
+        }
+    }
+
     /// 构造 string 类型
     pub fn new_string(&self, text: String) -> String {
         let len = text.len();
@@ -118,7 +231,7 @@ impl Limit {
                 .enumerate()
                 .map_while(|(i, ch)| if i < half { Some(*ch) } else { None })
                 .collect();
-            let mut m: Vec<_> = format!("...{}...", len - self.str_limit).chars().collect();
+            let mut m: Vec<_> = self.marker(len - self.str_limit).chars().collect();
             let mut r: Vec<_> = full
                 .iter()
                 .rev()
@@ -132,6 +245,51 @@ impl Limit {
         }
     }
 
+    /// 构造二进制数据, 输出 <{长度} bytes: xx xx ...>, 超过 str_limit 个字节时只保留前后各 str_limit / 2 个字节
+    ///
+    /// 与 new_string 不同, 只有 str_limit 为 0 时完整保留, 而不是 <= 10; str_limit 为 1 时只输出省略标记
+    pub fn new_string_bytes(&self, data: &[u8]) -> String {
+        let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ");
+        let len = data.len();
+
+        let text = if self.str_limit == 0 || len <= self.str_limit {
+            // 完整保留
+            hex(data)
+        } else if self.str_limit == 1 {
+            // 两侧都不保留
+            self.marker(len)
+        } else {
+            // {左 half} ...{skip}... {右 half}
+            let half = self.str_limit / 2;
+            format!(
+                "{} {} {}",
+                hex(&data[..half]),
+                self.marker(len - half * 2),
+                hex(&data[len - half..])
+            )
+        };
+
+        format!("<{} bytes: {}>", len, text)
+    }
+
+    /// 构造 struct 类型, fields 为 (字段名, 已压缩的值), 输出与 #[derive(LimitPack)] 相同
+    ///
+    /// 与 tuple 相同, 调用前后需配合 push_and_inc() / pop_start() / pop_end()
+    pub fn new_struct(&mut self, name: &str, fields: &[(&str, String)]) -> String {
+        // 左标识
+        let pair_seq = self.pair_seq;
+        let mut text = format!("{}{}{} ", name, '(', pair_seq);
+        self.pair_seq += 1;
+
+        for (i, (k, v)) in fields.iter().enumerate() {
+            text += &format!("{}:{}{}", k, v, if i < fields.len() - 1 { "," } else { "" });
+        }
+
+        // 右标识
+        text += &format!("{}{}{}", if fields.is_empty() { "" } else { " " }, pair_seq, ')');
+        text
+    }
+
     /// 构造 tuple 类型
     pub fn new_tuple(&mut self, data: &Vec<String>) -> String {
         // 左标识
@@ -170,6 +328,24 @@ impl Limit {
         self.pair_stack.push(self.pair_seq);
         self.pair_seq += 1;
     }
+
+    /// 设置 [T; N] 是否不受 array_limit 限制, 缺省为 false
+    pub fn with_fixed_arrays_unlimited(mut self, unlimited: bool) -> Self {
+        self.fixed_arrays_unlimited = unlimited;
+        self
+    }
+
+    /// 设置 Option 是否输出 Some(...), 缺省为 false, 只输出内部的值
+    pub fn with_show_some_wrapper(mut self, show: bool) -> Self {
+        self.show_some_wrapper = show;
+        self
+    }
+
+    /// 设置省略标识, 替换缺省的 ...{skip}...
+    pub fn with_truncation_marker(mut self, marker: String) -> Self {
+        self.truncation_marker = Some(marker);
+        self
+    }
 }
 
 pub struct ForStruct<T> {
@@ -186,6 +362,16 @@ pub trait LimitPackAble {
         let mut limit = Limit::new(array_limit, dict_limit, str_limit);
         self.to_limit_str(&mut limit)
     }
+
+    /// 同 to_limit_str3(10, 10, 80), 适合单行日志
+    fn to_limit_str_default(&self) -> String {
+        self.to_limit_str3(10, 10, 80)
+    }
+
+    /// 同 to_limit_str3(100, 100, 1000), 适合调试输出
+    fn to_limit_str_verbose(&self) -> String {
+        self.to_limit_str3(100, 100, 1000)
+    }
 }
 
 macro_rules! default_limit_pack {
@@ -204,6 +390,7 @@ macro_rules! default_limit_pack {
 
 default_limit_pack!(bool, true);
 default_limit_pack!(char, true);
+default_limit_pack!(Decimal, true);
 default_limit_pack!(f32, true);
 default_limit_pack!(f64, true);
 default_limit_pack!(i8, true);
@@ -221,6 +408,78 @@ default_limit_pack!(u128, true);
 default_limit_pack!(usize, true);
 default_limit_pack!(String, false);
 
+// 网络地址, 不截断
+default_limit_pack!(IpAddr, true);
+default_limit_pack!(Ipv4Addr, true);
+default_limit_pack!(Ipv6Addr, true);
+default_limit_pack!(SocketAddr, true);
+default_limit_pack!(SocketAddrV4, true);
+default_limit_pack!(SocketAddrV6, true);
+
+// 原子类型, 仅用于调试输出, Relaxed 即可
+macro_rules! atomic_limit_pack {
+    ($type:ident) => {
+        impl LimitPackAble for $type {
+            fn to_limit_str(&self, limit: &mut Limit) -> String {
+                self.load(Ordering::Relaxed).to_limit_str(limit)
+            }
+        }
+    };
+}
+
+atomic_limit_pack!(AtomicBool);
+atomic_limit_pack!(AtomicI8);
+atomic_limit_pack!(AtomicI16);
+atomic_limit_pack!(AtomicI32);
+atomic_limit_pack!(AtomicI64);
+atomic_limit_pack!(AtomicIsize);
+atomic_limit_pack!(AtomicU8);
+atomic_limit_pack!(AtomicU16);
+atomic_limit_pack!(AtomicU32);
+atomic_limit_pack!(AtomicU64);
+atomic_limit_pack!(AtomicUsize);
+
+impl LimitPackAble for Cow<'_, str> {
+    fn to_limit_str(&self, limit: &mut Limit) -> String {
+        limit.clone(limit.str_limit).new_string(self.to_string())
+    }
+}
+
+// 不足 1 秒显示为 NNNms, 否则显示为 N.NNNs, 不截断
+impl LimitPackAble for Duration {
+    fn to_limit_str(&self, limit: &mut Limit) -> String {
+        let text = if self.as_secs() == 0 {
+            format!("{}ms", self.as_millis())
+        } else {
+            format!("{:.3}s", self.as_secs_f64())
+        };
+        limit.clone(0).new_string(text)
+    }
+}
+
+// Instant 没有绝对时间, 显示距今的时长, 不截断
+impl LimitPackAble for Instant {
+    fn to_limit_str(&self, limit: &mut Limit) -> String {
+        let elapsed = Instant::now().saturating_duration_since(*self);
+        limit.clone(0).new_string(format!("Instant({}ms)", elapsed.as_millis()))
+    }
+}
+
+// 按北京时间显示, 不截断
+impl LimitPackAble for SystemTime {
+    fn to_limit_str(&self, limit: &mut Limit) -> String {
+        let timestamp = match self.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs_f64(),
+            Err(err) => -err.duration().as_secs_f64(),
+        };
+        let text = match bjtc_ft(timestamp) {
+            Ok(time) => bjtc_format(&time, "%Y-%m-%d %H:%M:%S"),
+            Err(_) => format!("{:?}", self),
+        };
+        limit.clone(0).new_string(text)
+    }
+}
+
 impl<T> LimitPackAble for &T
 where
     T: LimitPackAble + ?Sized,
@@ -239,11 +498,42 @@ where
     }
 }
 
+impl<T> LimitPackAble for Arc<T>
+where
+    T: LimitPackAble + ?Sized,
+{
+    fn to_limit_str(&self, limit: &mut Limit) -> String {
+        (**self).to_limit_str(limit)
+    }
+}
+
+impl<T> LimitPackAble for Box<T>
+where
+    T: LimitPackAble + ?Sized,
+{
+    fn to_limit_str(&self, limit: &mut Limit) -> String {
+        (**self).to_limit_str(limit)
+    }
+}
+
+impl<T> LimitPackAble for Rc<T>
+where
+    T: LimitPackAble + ?Sized,
+{
+    fn to_limit_str(&self, limit: &mut Limit) -> String {
+        (**self).to_limit_str(limit)
+    }
+}
+
 impl<T> LimitPackAble for Option<T>
 where
     T: LimitPackAble,
 {
     fn to_limit_str(&self, limit: &mut Limit) -> String {
+        if limit.show_some_wrapper {
+            return limit.new_option(self);
+        }
+
         match self {
             Some(obj) => obj.to_limit_str(limit),
             None => Limit::new(0, 0, 0).new_string("None".to_string()),
@@ -284,6 +574,9 @@ macro_rules! last_type {
     ($a:ident, $($rest_a:ident,)+) => { last_type!($($rest_a,)+) };
 }
 
+/// 实现了 LimitPackAble 的 tuple 最大长度, 与下面 tuple! 的参数个数一致
+pub const MAX_TUPLE_LIMIT_PACK: usize = 26;
+
 tuple! { A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, }
 
 impl<T> LimitPackAble for [T]
@@ -300,7 +593,11 @@ where
     T: LimitPackAble,
 {
     fn to_limit_str(&self, limit: &mut Limit) -> String {
-        limit.new_list(&self.iter().collect())
+        if limit.fixed_arrays_unlimited {
+            limit.new_fixed_array(self)
+        } else {
+            limit.new_list(&self.iter().collect())
+        }
     }
 }
 
@@ -322,6 +619,15 @@ where
     }
 }
 
+impl<T> LimitPackAble for BTreeSet<T>
+where
+    T: LimitPackAble,
+{
+    fn to_limit_str(&self, limit: &mut Limit) -> String {
+        limit.new_list(&self.iter().collect())
+    }
+}
+
 impl<T, S> LimitPackAble for HashSet<T, S>
 where
     T: LimitPackAble,
@@ -332,6 +638,16 @@ where
 }
 
 impl<K, V, S> LimitPackAble for HashMap<K, V, S>
+where
+    K: LimitPackAble,
+    V: LimitPackAble,
+{
+    fn to_limit_str(&self, limit: &mut Limit) -> String {
+        limit.new_dict_sorted(&self.iter().collect::<Vec<_>>())
+    }
+}
+
+impl<K, V> LimitPackAble for BTreeMap<K, V>
 where
     K: LimitPackAble,
     V: LimitPackAble,
@@ -349,3 +665,338 @@ where
         format!("{}:{}", self.k, self.v.to_limit_str(limit))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_atomic() {
+        let counter = AtomicU32::new(7);
+        counter.fetch_add(1, Ordering::Relaxed);
+        assert_eq!(counter.to_limit_str3(4, 4, 12), "8");
+        assert_eq!(AtomicI64::new(-5).to_limit_str3(4, 4, 12), "-5");
+        assert_eq!(AtomicBool::new(true).to_limit_str3(4, 4, 12), "true");
+        assert_eq!(
+            (AtomicUsize::new(1), vec![AtomicI8::new(2)]).to_limit_str3(4, 4, 12),
+            "(0 1,[1 2 1] 0)"
+        );
+    }
+
+    #[test]
+    fn test_btree() {
+        let map = (0..10).map(|i| (9 - i, i * 10)).collect::<BTreeMap<i32, i32>>();
+        let text = map.to_limit_str3(4, 4, 12);
+        assert_eq!(text, "{0 0:90,1:80,...6...8:10,9:0 0}");
+        for _ in 0..10 {
+            let map = (0..10).map(|i| (i, 90 - i * 10)).collect::<BTreeMap<i32, i32>>();
+            assert_eq!(map.to_limit_str3(4, 4, 12), text);
+        }
+
+        let set = (0..10).rev().collect::<BTreeSet<i32>>();
+        assert_eq!(
+            set.to_limit_str3(4, 4, 12),
+            (0..10).collect::<Vec<i32>>().to_limit_str3(4, 4, 12)
+        );
+        assert_eq!(set.to_limit_str3(4, 4, 12), "[0 0,1,...6...8,9 0]");
+        assert_eq!(BTreeSet::<i32>::new().to_limit_str3(4, 4, 12), "[0 0]");
+    }
+
+    #[test]
+    fn test_cow() {
+        let text = "01234567890123456789";
+        let borrowed: Cow<str> = Cow::Borrowed(text);
+        let owned: Cow<str> = Cow::Owned(text.to_string());
+
+        assert_eq!(borrowed.to_limit_str3(4, 4, 12), text.to_limit_str3(4, 4, 12));
+        assert_eq!(owned.to_limit_str3(4, 4, 12), "012345...8...456789");
+        assert_eq!((1, Cow::Borrowed("a")).to_limit_str3(4, 4, 12), "(0 1,a 0)");
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(Decimal::new(12345, 2).to_limit_str3(4, 4, 4), "123.45");
+        assert_eq!(
+            Decimal::new(-1234567890123456789, 9).to_limit_str3(4, 4, 12),
+            "-1234567890.123456789"
+        );
+        assert_eq!(
+            vec![Decimal::new(1, 1), Decimal::new(2, 1)].to_limit_str3(4, 4, 12),
+            "[0 0.1,0.2 0]"
+        );
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(Duration::from_millis(1500).to_limit_str_default(), "1.500s");
+        assert_eq!(Duration::from_millis(999).to_limit_str_default(), "999ms");
+        assert_eq!(Duration::from_micros(1500).to_limit_str_default(), "1ms");
+        assert_eq!(Duration::from_secs(3600).to_limit_str3(4, 4, 12), "3600.000s");
+        assert_eq!(
+            (Duration::from_millis(20), vec![Duration::from_secs(2)]).to_limit_str3(4, 4, 12),
+            "(0 20ms,[1 2.000s 1] 0)"
+        );
+
+        let start = Instant::now() - Duration::from_millis(50);
+        let text = start.to_limit_str_default();
+        let ms = text.trim_start_matches("Instant(").trim_end_matches("ms)");
+        assert!(ms.parse::<u128>().unwrap() >= 50, "{}", text);
+    }
+
+    #[test]
+    fn test_fixed_array() {
+        let data = [1, 2, 3];
+        assert_eq!(data.to_limit_str3(2, 4, 12), "[0 1,...1...3 0]");
+
+        let mut limit = Limit::new(2, 4, 12).with_fixed_arrays_unlimited(true);
+        assert_eq!(data.to_limit_str(&mut limit), "[0 1,2,3 0]");
+
+        // 仅 [T; N] 本身不受限制, 内部的 Vec 仍受限制
+        let mut limit = Limit::new(2, 4, 12).with_fixed_arrays_unlimited(true);
+        assert_eq!(
+            [vec![1, 2, 3], vec![4]].to_limit_str(&mut limit),
+            "[0 [1 1,...1...3 1],[2 4 2] 0]"
+        );
+        assert_eq!(Limit::new(2, 4, 12).new_fixed_array::<i32>(&[]), "[0 0]");
+    }
+
+    #[test]
+    fn test_hashmap() {
+        let map = (0..100)
+            .map(|i| (format!("k{:02}", i), i))
+            .collect::<HashMap<String, i32>>();
+        let text = map.to_limit_str3(4, 4, 12);
+        assert_eq!(text, "{0 k00:0,k01:1,...96...k98:98,k99:99 0}");
+        assert_eq!(map.to_limit_str3(4, 4, 12), text);
+        for _ in 0..10 {
+            let map = (0..100)
+                .rev()
+                .map(|i| (format!("k{:02}", i), i))
+                .collect::<HashMap<String, i32>>();
+            assert_eq!(map.to_limit_str3(4, 4, 12), text);
+        }
+
+        // 嵌套时 pair_seq 仍按输出顺序
+        let map = [(2, vec![2]), (1, vec![1])].into_iter().collect::<HashMap<_, _>>();
+        assert_eq!(map.to_limit_str3(4, 4, 12), "{0 1:[1 1 1],2:[2 2 2] 0}");
+        assert_eq!(HashMap::<i32, i32>::new().to_limit_str3(4, 4, 12), "{0 0}");
+    }
+
+    #[test]
+    fn test_limit_str_default() {
+        let text = "a".repeat(200);
+        assert_eq!(
+            text.to_limit_str_default(),
+            format!("{}...120...{}", "a".repeat(40), "a".repeat(40))
+        );
+        assert_eq!(text.to_limit_str_verbose(), text);
+
+        let data = (0..200).collect::<Vec<i32>>();
+        assert_eq!(data.to_limit_str_default(), data.to_limit_str3(10, 10, 80));
+        assert_eq!(data.to_limit_str_verbose(), data.to_limit_str3(100, 100, 1000));
+    }
+
+    #[test]
+    fn test_max_tuple() {
+        // 长度为 MAX_TUPLE_LIMIT_PACK 的 tuple, 减小 tuple! 的参数个数时编译失败
+        let data = (
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+        );
+        let expected = format!(
+            "(0 {} 0)",
+            (0..MAX_TUPLE_LIMIT_PACK)
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        assert_eq!(data.to_limit_str3(4, 4, 12), expected);
+    }
+
+    #[test]
+    fn test_net() {
+        let ip = "127.0.0.1".parse::<IpAddr>().unwrap();
+        assert_eq!(ip.to_limit_str3(4, 4, 12), ip.to_string());
+
+        let ipv6 = "2001:db8:85a3::8a2e:370:7334".parse::<Ipv6Addr>().unwrap();
+        assert_eq!(ipv6.to_limit_str3(4, 4, 4), "2001:db8:85a3::8a2e:370:7334");
+        assert_eq!(IpAddr::V6(ipv6).to_limit_str3(4, 4, 4), ipv6.to_string());
+        assert_eq!(Ipv4Addr::LOCALHOST.to_limit_str3(4, 4, 4), "127.0.0.1");
+
+        let addr = "[::1]:8080".parse::<SocketAddr>().unwrap();
+        assert_eq!(addr.to_limit_str3(4, 4, 4), "[::1]:8080");
+        let addr_v4 = "192.168.100.200:65535".parse::<SocketAddrV4>().unwrap();
+        assert_eq!(addr_v4.to_limit_str3(4, 4, 12), "192.168.100.200:65535");
+        let addr_v6 = "[fe80::1234:5678:9abc:def0]:443".parse::<SocketAddrV6>().unwrap();
+        assert_eq!(addr_v6.to_limit_str3(4, 4, 12), addr_v6.to_string());
+        assert_eq!(vec![addr_v4].to_limit_str3(4, 4, 12), "[0 192.168.100.200:65535 0]");
+    }
+
+    #[test]
+    fn test_new_named_struct() {
+        struct Abc {
+            a: i32,
+            b: &'static str,
+            d: Vec<Vec<i32>>,
+        }
+
+        impl LimitPackAble for Abc {
+            fn to_limit_str(&self, limit: &mut Limit) -> String {
+                limit.new_named_struct("Abc", &[("a", &self.a), ("b", &self.b), ("d", &self.d)])
+            }
+        }
+
+        let abc = Abc {
+            a: 1,
+            b: "1",
+            d: vec![vec![1]],
+        };
+        // 与 new_struct 的输出一致
+        assert_eq!(abc.to_limit_str3(4, 4, 12), "Abc(0 a:1,b:1,d:[1 [2 1 2] 1] 0)");
+        assert_eq!(
+            (1, abc).to_limit_str3(4, 4, 12),
+            "(0 1,Abc(1 a:1,b:1,d:[2 [3 1 3] 2] 1) 0)"
+        );
+        assert_eq!(Limit::new(4, 4, 12).new_named_struct("Empty", &[]), "Empty(0 0)");
+    }
+
+    #[test]
+    fn test_new_struct() {
+        struct Abc {
+            a: i32,
+            b: &'static str,
+            d: Vec<Vec<i32>>,
+            e: Vec<i32>,
+        }
+
+        impl LimitPackAble for Abc {
+            fn to_limit_str(&self, limit: &mut Limit) -> String {
+                limit.push_and_inc();
+                let fields = [
+                    ("a", self.a.to_limit_str(limit)),
+                    ("b", self.b.to_limit_str(limit)),
+                    ("d", self.d.to_limit_str(limit)),
+                    ("e", self.e.to_limit_str(limit)),
+                ];
+                let pair_seq = limit.pop_start();
+                let text = limit.new_struct("Abc", &fields);
+                limit.pop_end(pair_seq);
+                text
+            }
+        }
+
+        let abc = Abc {
+            a: 1,
+            b: "1",
+            d: vec![vec![1]],
+            e: vec![1],
+        };
+        // 与 #[derive(LimitPack)] 的输出一致
+        assert_eq!(
+            abc.to_limit_str3(4, 4, 12),
+            "Abc(0 a:1,b:1,d:[1 [2 1 2] 1],e:[3 1 3] 0)"
+        );
+        assert_eq!(
+            (1, abc).to_limit_str3(4, 4, 12),
+            "(0 1,Abc(1 a:1,b:1,d:[2 [3 1 3] 2],e:[4 1 4] 1) 0)"
+        );
+        assert_eq!(Limit::new(4, 4, 12).new_struct("Empty", &[]), "Empty(0 0)");
+    }
+
+    #[test]
+    fn test_option() {
+        let data = vec![Some("None"), None, Some("abc")];
+        assert_eq!(data.to_limit_str3(4, 4, 12), "[0 None,None,abc 0]");
+
+        let limit = || Limit::new(4, 4, 12).with_show_some_wrapper(true);
+        assert_eq!(data.to_limit_str(&mut limit()), "[0 Some(None),None,Some(abc) 0]");
+        assert_eq!(Some(vec![1, 2]).to_limit_str(&mut limit()), "Some([0 1,2 0])");
+        assert_eq!(Some(Some(1)).to_limit_str(&mut limit()), "Some(Some(1))");
+        assert_eq!(Limit::new(4, 4, 12).new_option::<i32>(&None), "None");
+    }
+
+    #[test]
+    fn test_smart_pointer() {
+        let data = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let expected = data.to_limit_str3(4, 4, 12);
+        assert_eq!(Arc::new(data.clone()).to_limit_str3(4, 4, 12), expected);
+        assert_eq!(Rc::new(data.clone()).to_limit_str3(4, 4, 12), expected);
+        assert_eq!(Box::new(data).to_limit_str3(4, 4, 12), expected);
+
+        let boxed: Box<str> = "hello".into();
+        assert_eq!(boxed.to_limit_str3(4, 4, 12), "hello");
+        assert_eq!(
+            Cow::Borrowed("hello").to_limit_str3(4, 4, 12),
+            "hello".to_limit_str3(4, 4, 12)
+        );
+        assert_eq!(Arc::new(Cow::Borrowed("hello")).to_limit_str3(4, 4, 12), "hello");
+    }
+
+    #[test]
+    fn test_string_bytes() {
+        let data = (0..20).collect::<Vec<u8>>();
+        assert_eq!(
+            Limit::new(4, 4, 10).new_string_bytes(&data),
+            "<20 bytes: 00 01 02 03 04 ...10... 0f 10 11 12 13>"
+        );
+        assert_eq!(Limit::new(4, 4, 30).new_string_bytes(&data[..3]), "<3 bytes: 00 01 02>");
+        assert_eq!(Limit::new(4, 4, 10).new_string_bytes(&[]), "<0 bytes: >");
+        assert_eq!(
+            Limit::new(4, 4, 4)
+                .with_truncation_marker("…".to_string())
+                .new_string_bytes(b"\xff\xfeabc"),
+            "<5 bytes: ff fe … 62 63>"
+        );
+
+        // str_limit 为 1 时没有可保留的字节, 为奇数时按 str_limit - 1 计
+        assert_eq!(Limit::new(4, 4, 1).new_string_bytes(&data), "<20 bytes: ...20...>");
+        assert_eq!(Limit::new(4, 4, 1).new_string_bytes(&data[..1]), "<1 bytes: 00>");
+        assert_eq!(
+            Limit::new(4, 4, 3).new_string_bytes(&data),
+            "<20 bytes: 00 ...18... 13>"
+        );
+        assert_eq!(
+            Limit::new(4, 4, 7).new_string_bytes(&data),
+            "<20 bytes: 00 01 02 ...14... 11 12 13>"
+        );
+        assert_eq!(
+            Limit::new(4, 4, 7).new_string_bytes(&data[..7]),
+            "<7 bytes: 00 01 02 03 04 05 06>"
+        );
+    }
+
+    #[test]
+    fn test_system_time() {
+        assert_eq!(UNIX_EPOCH.to_limit_str3(4, 4, 12), "1970-01-01 08:00:00");
+
+        let time = UNIX_EPOCH + Duration::from_millis(1_705_280_400_500);
+        assert_eq!(time.to_limit_str3(4, 4, 12), "2024-01-15 09:00:00");
+        assert_eq!(
+            (time, vec![time]).to_limit_str3(4, 4, 12),
+            "(0 2024-01-15 09:00:00,[1 2024-01-15 09:00:00 1] 0)"
+        );
+
+        let time = UNIX_EPOCH - Duration::from_secs(3600);
+        assert_eq!(time.to_limit_str3(4, 4, 12), "1970-01-01 07:00:00");
+    }
+
+    #[test]
+    fn test_truncation_marker() {
+        let mut limit = Limit::new(4, 4, 12).with_truncation_marker("…".to_string());
+        assert_eq!(limit.new_string("01234567890123456789".to_string()), "012345…456789");
+        assert_eq!((0..10).collect::<Vec<i32>>().to_limit_str(&mut limit), "[0 0,1,…8,9 0]");
+
+        let mut limit = Limit::new(4, 4, 12).with_truncation_marker("[…]".to_string());
+        let map = (0..10).map(|i| (i, i * 10)).collect::<BTreeMap<i32, i32>>();
+        assert_eq!(map.to_limit_str(&mut limit), "{0 0:0,1:10,[…]8:80,9:90 0}");
+
+        // 嵌套的字符串同样使用
+        let mut limit = Limit::new(4, 4, 12).with_truncation_marker("~".to_string());
+        assert_eq!(
+            vec!["01234567890123456789"].to_limit_str(&mut limit),
+            "[0 012345~456789 0]"
+        );
+
+        // 缺省不变
+        assert_eq!("01234567890123456789".to_limit_str3(4, 4, 12), "012345...8...456789");
+    }
+}
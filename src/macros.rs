@@ -1,3 +1,5 @@
+use std::{fmt, time::Instant};
+
 /// Current version number
 ///
 /// ## Usage
@@ -16,10 +18,41 @@ macro_rules! crate_version {
     };
 }
 
+/// 条件不满足时, 从当前函数返回 Err(MoreError)
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr, $func:ident, $text:expr) => {
+        if !($cond) {
+            return Err(MoreError::new(file!(), line!(), $func, $text));
+        }
+    };
+}
+
+/// 提取 Result 中的内容, 或增加一层 MoreError 后从当前函数返回, 同 .m(m!(func, text))?
+#[macro_export]
+macro_rules! ensure_ok {
+    ($e:expr, $func:ident, $text:expr) => {
+        $e.m(m!($func, $text))?
+    };
+}
+
+/// 提取 Option 中的内容, 或从当前函数返回 Err(MoreError)
+#[macro_export]
+macro_rules! ensure_some {
+    ($e:expr, $func:ident, $text:expr) => {
+        match $e {
+            Some(e) => e,
+            None => return Err(MoreError::new(file!(), line!(), $func, $text)),
+        }
+    };
+}
+
 /// 用最短的代码引入文件名、行号
 /// 1. 仅函数名
 /// 2. 函数名 + 补充信息
 /// 3. 函数名 + 补充信息, 直接构造
+///
+/// 补充信息可以是 &str 或 String
 #[macro_export]
 macro_rules! m {
     ($func:ident) => {
@@ -36,6 +69,28 @@ macro_rules! m {
     };
 }
 
+/// 同 m!(func, text, "more"), 同时设置错误码
+#[macro_export]
+macro_rules! m_code {
+    ($func:ident, $text:expr, $code:expr) => {
+        MoreError::new(file!(), line!(), $func, $text).with_code($code)
+    };
+}
+
+/// 提取 Result 中的内容为 Some, 或输出 Err 到 stderr 并返回 None, 同 log_err()
+#[macro_export]
+macro_rules! ok_or_log {
+    ($e:expr) => {
+        match $e {
+            Ok(e) => Some(e),
+            Err(err) => {
+                eprintln!("{}", $crate::ok_or_log_message(&err));
+                None
+            }
+        }
+    };
+}
+
 /// 提取 Result 中的内容, 或从当前函数返回
 #[macro_export]
 macro_rules! ok_or_return {
@@ -47,6 +102,57 @@ macro_rules! ok_or_return {
     };
 }
 
+/// 同 ok_or_return!, 返回前输出 Err 及文件名、行号到 stderr
+#[macro_export]
+macro_rules! ok_or_return_log {
+    ($e:expr, $r:expr) => {
+        match $e {
+            Ok(e) => e,
+            Err(err) => {
+                eprintln!("{}", $crate::ok_or_return_log_message(file!(), line!(), &err));
+                return $r;
+            }
+        }
+    };
+}
+
+/// 构造 MoreError, 同 m!(func, text, "result") / m!(func, text, "more")
+/// 1. raise_error!(func, text): Err(MoreError)
+/// 2. raise_error!(func, text, "raw"): MoreError
+/// 3. raise_error!(func, text, "more"): MoreError
+///
+/// ```
+/// use python_comm::use_m::*;
+///
+/// #[auto_func_name]
+/// fn check(n: i32) -> Result<i32, MoreError> {
+///     if n < 0 {
+///         return raise_error!(fname, format!("n={}", n));
+///     }
+///     Ok(n)
+/// }
+///
+/// assert!(check(-1).unwrap_err().to_string().ends_with("check() n=-1"));
+///
+/// let fname = "main";
+/// let raw: MoreError = raise_error!(fname, "abc", "raw");
+/// let more: MoreError = raise_error!(fname, "abc", "more");
+/// assert!(raw.to_string().ends_with("main() abc"));
+/// assert!(more.to_string().ends_with("main() abc"));
+/// ```
+#[macro_export]
+macro_rules! raise_error {
+    ($func:ident, $text:expr) => {
+        $crate::m!($func, $text, "result")
+    };
+    ($func:ident, $text:expr, "raw") => {
+        $crate::m!($func, $text, "more")
+    };
+    ($func:ident, $text:expr, "more") => {
+        $crate::m!($func, $text, "more")
+    };
+}
+
 /// 提取 Option 中的内容, 或从当前函数返回
 #[macro_export]
 macro_rules! some_or_return {
@@ -57,3 +163,112 @@ macro_rules! some_or_return {
         }
     };
 }
+
+/// 同 some_or_return!, 返回前输出文件名、行号到 stderr
+#[macro_export]
+macro_rules! some_or_return_log {
+    ($e:expr, $r:expr) => {
+        match $e {
+            Some(e) => e,
+            None => {
+                eprintln!("{}", $crate::some_or_return_log_message(file!(), line!()));
+                return $r;
+            }
+        }
+    };
+}
+
+/// Evaluate an expression, print its elapsed time to stderr as "label: Nms", and return its value
+///
+/// ## Usage
+///
+/// ```
+/// use python_comm::use_basic::*;
+/// use std::{thread::sleep, time::{Duration, Instant}};
+///
+/// let start = Instant::now();
+/// let x = timed!("compute_x", {
+///     sleep(Duration::from_millis(50));
+///     1 + 2
+/// });
+/// assert_eq!(x, 3);
+/// assert!(start.elapsed() >= Duration::from_millis(50));
+/// ```
+///
+#[macro_export]
+macro_rules! timed {
+    ($label:expr, $e:expr) => {{
+        let start = std::time::Instant::now();
+        let value = $e;
+        eprintln!("{}", $crate::timed_message($label, start));
+        value
+    }};
+}
+
+/// ok_or_log! 输出的内容
+#[doc(hidden)]
+pub fn ok_or_log_message<E>(err: &E) -> String
+where
+    E: fmt::Display,
+{
+    err.to_string()
+}
+
+/// ok_or_return_log! 输出的内容
+#[doc(hidden)]
+pub fn ok_or_return_log_message<E>(file: &str, line: u32, err: &E) -> String
+where
+    E: fmt::Debug,
+{
+    format!("{}:{} {:?}", file, line, err)
+}
+
+/// some_or_return_log! 输出的内容
+#[doc(hidden)]
+pub fn some_or_return_log_message(file: &str, line: u32) -> String {
+    format!("{}:{} unexpected None", file, line)
+}
+
+/// timed! 输出的内容, 耗时从 start 算起
+#[doc(hidden)]
+pub fn timed_message<L>(label: L, start: Instant) -> String
+where
+    L: fmt::Display,
+{
+    format!("{}: {}ms", label, start.elapsed().as_millis())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{thread::sleep, time::Duration};
+
+    #[test]
+    fn test_ok_or_log_message() {
+        assert_eq!(ok_or_log_message(&"abc"), "abc");
+        assert_eq!(ok_or_log_message(&crate::use_m::MoreError::message("a\nb")), "a\nb");
+    }
+
+    #[test]
+    fn test_ok_or_return_log_message() {
+        assert_eq!(ok_or_return_log_message("a.rs", 12, &"abc"), "a.rs:12 \"abc\"");
+    }
+
+    #[test]
+    fn test_some_or_return_log_message() {
+        assert_eq!(some_or_return_log_message("a.rs", 12), "a.rs:12 unexpected None");
+    }
+
+    #[test]
+    fn test_timed_message() {
+        let start = Instant::now();
+        sleep(Duration::from_millis(50));
+        let message = timed_message("compute_x", start);
+
+        let ms = message
+            .strip_prefix("compute_x: ")
+            .and_then(|x| x.strip_suffix("ms"))
+            .unwrap();
+        assert!(ms.parse::<u128>().unwrap() >= 50, "{}", message);
+    }
+}
@@ -1,18 +1,74 @@
+#[cfg(feature = "use_serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{error::Error, fmt};
 
+/// MoreError::description() 的返回值, 用于在 E 不是 'static 时识别 MoreError
+const MORE_ERROR_DESCRIPTION: &str = "python_comm::MoreError";
+
 /// 包含更多信息的 Error: 每次调用的文件名、行号、函数名、可选的附加内容
 pub struct MoreError {
     text: String,
+
+    /// 最初的 Error, 用于 source() 及 downcast_ref
+    source: Option<Box<dyn Error + Send + Sync>>,
+
+    /// 错误码, 便于程序判断错误类别, 包装后保留
+    code: Option<i32>,
 }
 
 impl MoreError {
-    /// 从 Error 构造
+    /// 错误码, 见 with_code
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+
+    /// 层数, 即 to_vec() 的长度
+    pub fn depth(&self) -> usize {
+        self.text.split('\n').count()
+    }
+
+    /// 从 Display 的结果构造, [E{code}] 前缀还原为错误码
+    #[cfg(feature = "use_serde")]
+    fn from_display(text: String) -> Self {
+        let code = text
+            .strip_prefix("[E")
+            .and_then(|rest| rest.split_once("] "))
+            .and_then(|(code, rest)| code.parse().ok().map(|code| (code, rest.to_string())));
+        match code {
+            Some((code, text)) => Self::from(text).with_code(code),
+            None => Self::from(text),
+        }
+    }
+
+    /// 从 MoreError 的 Display 及 Debug 还原, Debug 只含 text (带引号转义),
+    /// 仅当 Display 恰为 [E{code}] 前缀加 text 时才还原错误码, 避免把 text 中的 [E{code}] 当作错误码
+    fn from_display_debug(display: String, debug: &str) -> Self {
+        if let Some((code, text)) = display.strip_prefix("[E").and_then(|rest| rest.split_once("] ")) {
+            if let Ok(code) = code.parse() {
+                if format!("{:?}", text) == debug {
+                    return Self::from(text.to_string()).with_code(code);
+                }
+            }
+        }
+        Self::from(display)
+    }
+
+    /// 从 Error 构造, Error 是 MoreError 时同 from_more
     fn from_error<E>(err: &E, file: &str, line: u32, func: &str, text: &str) -> Self
     where
-        E: fmt::Debug,
+        E: Error,
     {
+        // E 不一定是 'static, 不能 downcast, 由 description() 识别 MoreError, 再从 Display 及 Debug 还原
+        #[allow(deprecated)]
+        if err.description() == MORE_ERROR_DESCRIPTION {
+            let err = Self::from_display_debug(err.to_string(), &format!("{:?}", err));
+            return Self::from_more(&err, file, line, func, text);
+        }
+
         Self {
             text: format!("Error: {}:{:3} {}() {}\nError: {:?}", file, line, func, text, err),
+            source: None,
+            code: None,
         }
     }
 
@@ -20,15 +76,71 @@ impl MoreError {
     fn from_more(err: &Self, file: &str, line: u32, func: &str, text: &str) -> Self {
         Self {
             text: format!("Error: {}:{:3} {}() {}\n{}", file, line, func, text, err.text),
+            source: None,
+            code: err.code,
+        }
+    }
+
+    /// 仅含 text, 不含文件名、行号、函数名, 用于自定义的错误
+    pub fn message(text: impl AsRef<str>) -> Self {
+        Self {
+            text: text.as_ref().to_string(),
+            source: None,
+            code: None,
         }
     }
 
     /// 从零构造
-    pub fn new(file: &str, line: u32, func: &str, text: &str) -> Self {
+    pub fn new(file: &str, line: u32, func: &str, text: impl AsRef<str>) -> Self {
         Self {
-            text: format!("Error: {}:{:3} {}() {}", file, line, func, text),
+            text: format!("Error: {}:{:3} {}() {}", file, line, func, text.as_ref()),
+            source: None,
+            code: None,
         }
     }
+
+    /// 在末尾追加一行, 不增加新的一层, 比如重试时记录每次的结果
+    pub fn push(&mut self, file: &str, line: u32, func: &str, text: impl AsRef<str>) {
+        self.text += &format!("\nError: {}:{:3} {}() {}", file, line, func, text.as_ref());
+    }
+
+    /// 同 push, 参数由 m! 生成, 可链式调用
+    pub fn push_m<S>(mut self, file_line_func_text: (&str, u32, &str, S)) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.push(
+            file_line_func_text.0,
+            file_line_func_text.1,
+            file_line_func_text.2,
+            file_line_func_text.3,
+        );
+        self
+    }
+
+    /// 按层拆分, 第一个为最外层, 最后一个为最内层
+    pub fn to_vec(&self) -> Vec<String> {
+        self.text.split('\n').map(String::from).collect()
+    }
+
+    /// 设置错误码, Display 时显示为 [E{code}] 前缀
+    pub fn with_code(mut self, code: i32) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// 保存最初的 Error, err 是 MoreError 时沿用其 source
+    fn with_source<E>(mut self, err: E) -> Self
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        let err: Box<dyn Error + Send + Sync> = Box::new(err);
+        self.source = match err.downcast::<Self>() {
+            Ok(err) => err.source,
+            Err(err) => Some(err),
+        };
+        self
+    }
 }
 
 impl fmt::Debug for MoreError {
@@ -39,7 +151,118 @@ impl fmt::Debug for MoreError {
 
 impl fmt::Display for MoreError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.text.fmt(f)
+        match self.code {
+            Some(code) => write!(f, "[E{}] {}", code, self.text),
+            None => self.text.fmt(f),
+        }
+    }
+}
+
+impl Error for MoreError {
+    fn description(&self) -> &str {
+        MORE_ERROR_DESCRIPTION
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.source {
+            Some(err) => Some(err.as_ref()),
+            None => None,
+        }
+    }
+}
+
+impl From<&str> for MoreError {
+    fn from(text: &str) -> Self {
+        Self::message(text)
+    }
+}
+
+impl From<String> for MoreError {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            source: None,
+            code: None,
+        }
+    }
+}
+
+/// 收集多个 MoreError, 用于批量处理时不在第一个错误处停止
+#[derive(Default)]
+pub struct MoreErrors {
+    errors: Vec<MoreError>,
+}
+
+impl MoreErrors {
+    /// 没有错误时返回 Ok(()), 只有一个时返回它本身, 否则合并为一个 MoreError
+    ///
+    /// 合并后 code() 为 None, 各自的错误码以 [E{code}] 前缀保留在文本中
+    pub fn into_result(mut self) -> Result<(), MoreError> {
+        match self.errors.len() {
+            0 => Ok(()),
+            1 => Err(self.errors.remove(0)),
+            _ => Err(MoreError::message(
+                self.errors
+                    .iter()
+                    .map(|err| err.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )),
+        }
+    }
+
+    /// 是否没有错误
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// 错误个数
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// 构造
+    pub fn new() -> Self {
+        Self { errors: Vec::new() }
+    }
+
+    /// 添加错误
+    pub fn push(&mut self, err: MoreError) {
+        self.errors.push(err);
+    }
+}
+
+impl fmt::Display for MoreErrors {
+    /// 每个错误之间空一行
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, err) in self.errors.iter().enumerate() {
+            if i > 0 {
+                write!(f, "\n\n")?;
+            }
+            write!(f, "{}", err)?;
+        }
+        Ok(())
+    }
+}
+
+/// 序列化为 Display 的文本, 错误码为 [E{code}] 前缀, source 不保存
+#[cfg(feature = "use_serde")]
+impl Serialize for MoreError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "use_serde")]
+impl<'de> Deserialize<'de> for MoreError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Self::from_display)
     }
 }
 
@@ -51,16 +274,22 @@ pub trait AddMoreError<T> {
         F: Fn() -> String;
 
     /// 附加文件名、行号、函数名、附加说明, 生成 MoreError
-    fn m(self, file_line_func_text: (&str, u32, &str, &str)) -> Result<T, MoreError>;
+    fn m<S>(self, file_line_func_text: (&str, u32, &str, S)) -> Result<T, MoreError>
+    where
+        S: AsRef<str>;
 
     /// 附加文件名、行号、函数名、附加说明, 输出
-    fn p(&self, file_line_func_text: (&str, u32, &str, &str));
+    fn p<S>(&self, file_line_func_text: (&str, u32, &str, S))
+    where
+        S: AsRef<str>;
 }
 
 /// 给 Error, ... 增加更多信息, 但抛弃 Error, ... 自身的内容
 pub trait AsMoreError<T> {
     /// 附加文件名、行号、函数名、附加说明, 抛弃 Error 自身的内容
-    fn as_m(self, file_line_func_text: (&str, u32, &str, &str)) -> Result<T, MoreError>;
+    fn as_m<S>(self, file_line_func_text: (&str, u32, &str, S)) -> Result<T, MoreError>
+    where
+        S: AsRef<str>;
 
     /// 转化为 MoreError, 不附加信息
     fn to_m(self) -> Result<T, MoreError>;
@@ -72,6 +301,20 @@ pub trait LessError<T, E> {
     fn l(self) -> Result<T, E>;
 }
 
+/// 输出 Error 但不传递
+pub trait LogError<T> {
+    /// Ok(T) -> Some(T), Err(*) -> 输出到 stderr, None
+    fn log_err(self) -> Option<T>;
+}
+
+/// 给 Error 增加更多信息, 并保留最初的 Error, 用于 source() 及 downcast_ref
+pub trait SourceMoreError<T> {
+    /// 同 m(), 但要求 Error 是 Send + Sync + 'static; 包装 MoreError 时沿用其 source, 每层都用 ms() 才能一直保留
+    fn ms<S>(self, file_line_func_text: (&str, u32, &str, S)) -> Result<T, MoreError>
+    where
+        S: AsRef<str>;
+}
+
 impl<T, E> AddMoreError<T> for Result<T, E>
 where
     E: Error,
@@ -93,20 +336,26 @@ where
     }
 
     /// 附加文件名、行号、函数名、附加说明
-    fn m(self, file_line_func_text: (&str, u32, &str, &str)) -> Result<T, MoreError> {
+    fn m<S>(self, file_line_func_text: (&str, u32, &str, S)) -> Result<T, MoreError>
+    where
+        S: AsRef<str>,
+    {
         self.map_err(|err| {
             MoreError::from_error(
                 &err,
                 file_line_func_text.0,
                 file_line_func_text.1,
                 file_line_func_text.2,
-                file_line_func_text.3,
+                file_line_func_text.3.as_ref(),
             )
         })
     }
 
     /// 附加文件名、行号、函数名、附加说明
-    fn p(&self, file_line_func_text: (&str, u32, &str, &str)) {
+    fn p<S>(&self, file_line_func_text: (&str, u32, &str, S))
+    where
+        S: AsRef<str>,
+    {
         if let Err(err) = self {
             println!(
                 "{}",
@@ -115,7 +364,7 @@ where
                     file_line_func_text.0,
                     file_line_func_text.1,
                     file_line_func_text.2,
-                    file_line_func_text.3,
+                    file_line_func_text.3.as_ref(),
                 )
             );
         }
@@ -141,53 +390,48 @@ where
     }
 
     /// 附加文件名、行号、函数名、附加说明
-    fn m(self, file_line_func_text: (&str, u32, &str, &str)) -> Result<T, MoreError> {
+    fn m<S>(self, file_line_func_text: (&str, u32, &str, S)) -> Result<T, MoreError>
+    where
+        S: AsRef<str>,
+    {
         Err(MoreError::from_error(
             self,
             file_line_func_text.0,
             file_line_func_text.1,
             file_line_func_text.2,
-            file_line_func_text.3,
+            file_line_func_text.3.as_ref(),
         ))
     }
 
     /// 附加文件名、行号、函数名、附加说明
-    fn p(&self, file_line_func_text: (&str, u32, &str, &str)) {
+    fn p<S>(&self, file_line_func_text: (&str, u32, &str, S))
+    where
+        S: AsRef<str>,
+    {
         println!(
             "{}",
             MoreError::from_error(
-                self,
+                *self,
                 file_line_func_text.0,
                 file_line_func_text.1,
                 file_line_func_text.2,
-                file_line_func_text.3,
+                file_line_func_text.3.as_ref(),
             )
         );
     }
 }
 
-impl<T> AddMoreError<T> for Result<T, MoreError> {
-    /// 附加文件名、行号、函数名、附加说明
-    fn f<F>(self, file_line_func_func: (&str, u32, &str, F)) -> Result<T, MoreError>
+impl<T, E> AsMoreError<T> for Result<T, E>
+where
+    E: fmt::Debug,
+{
+    /// 附加文件名、行号、函数名、附加说明, 抛弃 Error 自身的内容
+    fn as_m<S>(self, file_line_func_text: (&str, u32, &str, S)) -> Result<T, MoreError>
     where
-        F: Fn() -> String,
+        S: AsRef<str>,
     {
-        self.map_err(|err| {
-            MoreError::from_more(
-                &err,
-                file_line_func_func.0,
-                file_line_func_func.1,
-                file_line_func_func.2,
-                &file_line_func_func.3(),
-            )
-        })
-    }
-
-    /// 附加文件名、行号、函数名、附加说明
-    fn m(self, file_line_func_text: (&str, u32, &str, &str)) -> Result<T, MoreError> {
-        self.map_err(|err| {
-            MoreError::from_more(
-                &err,
+        self.map_err(|_| {
+            MoreError::new(
                 file_line_func_text.0,
                 file_line_func_text.1,
                 file_line_func_text.2,
@@ -196,91 +440,406 @@ impl<T> AddMoreError<T> for Result<T, MoreError> {
         })
     }
 
-    /// 附加文件名、行号、函数名、附加说明
-    fn p(&self, file_line_func_text: (&str, u32, &str, &str)) {
-        if let Err(err) = self {
-            println!(
-                "{}",
-                MoreError::from_more(
-                    err,
-                    file_line_func_text.0,
-                    file_line_func_text.1,
-                    file_line_func_text.2,
-                    file_line_func_text.3,
-                )
-            );
-        }
+    /// 转化为 MoreError, 不附加信息
+    fn to_m(self) -> Result<T, MoreError> {
+        self.map_err(|err| MoreError {
+            text: format!("{:?}", err),
+            source: None,
+            code: None,
+        })
     }
 }
 
-impl<T> AddMoreError<T> for &MoreError {
-    /// 附加文件名、行号、函数名、附加说明
-    fn f<F>(self, file_line_func_func: (&str, u32, &str, F)) -> Result<T, MoreError>
-    where
-        F: Fn() -> String,
-    {
-        Err(MoreError::from_more(
-            self,
-            file_line_func_func.0,
-            file_line_func_func.1,
-            file_line_func_func.2,
-            &file_line_func_func.3(),
-        ))
-    }
-
-    /// 附加文件名、行号、函数名、附加说明
-    fn m(self, file_line_func_text: (&str, u32, &str, &str)) -> Result<T, MoreError> {
-        Err(MoreError::from_more(
-            self,
-            file_line_func_text.0,
-            file_line_func_text.1,
-            file_line_func_text.2,
-            file_line_func_text.3,
-        ))
+impl<T, E> LessError<T, E> for Result<Result<T, E>, E> {
+    /// Ok(Result) -> Result, Err(*) -> Err(*)
+    fn l(self) -> Result<T, E> {
+        self.and_then(|x| x)
     }
+}
 
-    /// 附加文件名、行号、函数名、附加说明
-    fn p(&self, file_line_func_text: (&str, u32, &str, &str)) {
-        println!(
-            "{}",
-            MoreError::from_more(
-                self,
-                file_line_func_text.0,
-                file_line_func_text.1,
-                file_line_func_text.2,
-                file_line_func_text.3,
-            )
-        );
+impl<T, E> LogError<T> for Result<T, E>
+where
+    E: fmt::Display,
+{
+    /// Ok(T) -> Some(T), Err(*) -> 输出到 stderr, None
+    fn log_err(self) -> Option<T> {
+        crate::ok_or_log!(self)
     }
 }
 
-impl<T, E> AsMoreError<T> for Result<T, E>
+impl<T, E> SourceMoreError<T> for Result<T, E>
 where
-    E: fmt::Debug,
+    E: Error + Send + Sync + 'static,
 {
-    /// 附加文件名、行号、函数名、附加说明, 抛弃 Error 自身的内容
-    fn as_m(self, file_line_func_text: (&str, u32, &str, &str)) -> Result<T, MoreError> {
-        self.map_err(|_| {
-            MoreError::new(
+    /// 附加文件名、行号、函数名、附加说明, 保留最初的 Error
+    fn ms<S>(self, file_line_func_text: (&str, u32, &str, S)) -> Result<T, MoreError>
+    where
+        S: AsRef<str>,
+    {
+        self.map_err(|err| {
+            MoreError::from_error(
+                &err,
                 file_line_func_text.0,
                 file_line_func_text.1,
                 file_line_func_text.2,
-                file_line_func_text.3,
+                file_line_func_text.3.as_ref(),
             )
+            .with_source(err)
         })
     }
+}
 
-    /// 转化为 MoreError, 不附加信息
-    fn to_m(self) -> Result<T, MoreError> {
-        self.map_err(|err| MoreError {
-            text: format!("{:?}", err),
-        })
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn test_box_dyn_error() {
+        fn has_error() -> Result<(), MoreError> {
+            let fname = "has_error";
+            m!(fname, "id=5", "result")
+        }
+
+        fn propagate() -> Result<(), Box<dyn Error>> {
+            let fname = "propagate";
+            has_error().m(m!(fname))?;
+            Ok(())
+        }
+
+        let fname = "test_box_dyn_error";
+        let err: Box<dyn Error> = Box::new(m!(fname, "abc", "more"));
+        assert!(err.to_string().ends_with("test_box_dyn_error() abc"));
+
+        let err = propagate().unwrap_err();
+        let text = err.to_string();
+        assert!(text.lines().next().unwrap().ends_with("propagate() "));
+        assert!(text.lines().nth(1).unwrap().ends_with("has_error() id=5"));
+        assert!(err.downcast_ref::<MoreError>().is_some());
     }
-}
 
-impl<T, E> LessError<T, E> for Result<Result<T, E>, E> {
-    /// Ok(Result) -> Result, Err(*) -> Err(*)
-    fn l(self) -> Result<T, E> {
-        self.and_then(|x| x)
+    #[test]
+    fn test_code() {
+        let fname = "test_code";
+        let err = m!(fname, "abc", "more");
+        assert_eq!(err.code(), None);
+        assert!(err.to_string().starts_with("Error: "));
+
+        let err = crate::m_code!(fname, "abc", 404);
+        assert_eq!(err.code(), Some(404));
+        assert!(err.to_string().starts_with("[E404] Error: "));
+        assert!(err.to_string().ends_with("test_code() abc"));
+
+        // 包装后保留
+        let err = Err::<(), MoreError>(err)
+            .m(m!(fname, "outer"))
+            .m(m!(fname))
+            .unwrap_err();
+        assert_eq!(err.code(), Some(404));
+        assert_eq!(err.depth(), 3);
+        assert!(err.to_string().starts_with("[E404] Error: "));
+        assert_eq!(err.to_string().matches("[E404]").count(), 1);
+
+        let err = m!(fname, "abc", "more").with_code(1).with_code(-2);
+        assert_eq!(err.code(), Some(-2));
+        assert!(err.to_string().starts_with("[E-2] "));
+
+        // text 本身以 [E{code}] 开头时, 包装后不当作错误码
+        let err = Err::<(), MoreError>(MoreError::message("[E12] boom"))
+            .m(m!(fname))
+            .unwrap_err();
+        assert_eq!(err.code(), None);
+        assert_eq!(err.to_vec()[1], "[E12] boom");
+
+        let err = Err::<(), MoreError>(MoreError::message("[E12] boom").with_code(3))
+            .m(m!(fname))
+            .unwrap_err();
+        assert_eq!(err.code(), Some(3));
+        assert!(err.to_string().starts_with("[E3] Error: "));
+        assert_eq!(err.to_vec()[1], "[E12] boom");
+    }
+
+    #[test]
+    fn test_ensure() {
+        let fname = "test_ensure";
+
+        // 补充信息以 line=行号 结尾, 检查错误位置与之一致
+        let assert_location = |err: MoreError| {
+            let text = err.to_string();
+            let line = text.lines().next().unwrap().rsplit("line=").next().unwrap();
+            let location = format!("{}:{:3} test_ensure()", file!(), line.parse::<u32>().unwrap());
+            assert!(text.starts_with(&format!("Error: {}", location)), "{}", text);
+        };
+
+        let check = |n: i32| -> Result<(), MoreError> {
+            crate::ensure!(n > 0, fname, format!("line={}", line!()));
+            Ok(())
+        };
+        assert!(check(1).is_ok());
+        assert_location(check(0).unwrap_err());
+
+        let check = |x: Option<i32>| -> Result<i32, MoreError> {
+            let x = crate::ensure_some!(x, fname, format!("line={}", line!()));
+            Ok(x + 1)
+        };
+        assert_eq!(check(Some(1)).unwrap(), 2);
+        assert_location(check(None).unwrap_err());
+
+        let check = |x: Result<i32, MoreError>| -> Result<i32, MoreError> {
+            let x = crate::ensure_ok!(x, fname, format!("line={}", line!()));
+            Ok(x + 1)
+        };
+        assert_eq!(check(Ok(1)).unwrap(), 2);
+        let err = check(m!(fname, "abc", "result")).unwrap_err();
+        assert!(err.to_string().contains("test_ensure() abc"));
+        assert_location(err);
+    }
+
+    #[test]
+    fn test_log_err() {
+        let fname = "test_log_err";
+        let ok: Result<i32, MoreError> = Ok(1);
+        let err: Result<i32, MoreError> = m!(fname, "abc", "result");
+
+        assert_eq!(ok.log_err(), Some(1));
+        assert_eq!(err.log_err(), None);
+        assert_eq!(crate::ok_or_log!(Ok::<i32, MoreError>(2)), Some(2));
+        assert_eq!(crate::ok_or_log!(m!(fname, "abc", "result").map(|x: i32| x + 1)), None);
+
+        // 可以继续处理
+        let total = (0..4)
+            .map(|i| if i % 2 == 0 { Ok(i) } else { m!(fname, "odd", "result") })
+            .filter_map(|x| x.log_err())
+            .sum::<i32>();
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn test_message() {
+        fn check(n: i32) -> Result<i32, MoreError> {
+            if n < 0 {
+                return Err("invalid input".into());
+            }
+            if n == 0 {
+                return Err(format!("zero: {}", n).into());
+            }
+            Ok(n)
+        }
+
+        assert_eq!(check(1).unwrap(), 1);
+        assert_eq!(check(-1).unwrap_err().to_string(), "invalid input");
+        assert_eq!(check(0).unwrap_err().to_string(), "zero: 0");
+        assert_eq!(MoreError::message("abc").to_string(), "abc");
+        assert_eq!(MoreError::from(String::from("abc")).to_string(), "abc");
+
+        // 继续附加信息
+        let fname = "test_message";
+        let text = check(-1).m(m!(fname)).unwrap_err().to_string();
+        assert!(text.lines().next().unwrap().ends_with("test_message() "));
+        assert_eq!(text.lines().nth(1).unwrap(), "invalid input");
+    }
+
+    #[test]
+    fn test_more_errors() {
+        let fname = "test_more_errors";
+        let mut errors = MoreErrors::new();
+        assert!(errors.is_empty());
+        assert!(MoreErrors::default().into_result().is_ok());
+
+        for i in 0..3 {
+            let result: Result<(), MoreError> = m!(fname, format!("item {}", i), "result");
+            if let Err(err) = result.m(m!(fname)) {
+                errors.push(err);
+            }
+        }
+        assert_eq!(errors.len(), 3);
+        assert!(!errors.is_empty());
+
+        let text = errors.to_string();
+        assert_eq!(text.split("\n\n").count(), 3);
+
+        let err = errors.into_result().unwrap_err();
+        assert_eq!(err.depth(), 6);
+        for i in 0..3 {
+            assert!(err.to_vec()[i * 2 + 1].ends_with(&format!("test_more_errors() item {}", i)));
+        }
+
+        // 合并后不保留错误码, 但文本中有 [E{code}] 前缀
+        let mut errors = MoreErrors::new();
+        errors.push(crate::m_code!(fname, "a", 7));
+        errors.push(crate::m_code!(fname, "b", 8));
+        let err = errors.into_result().unwrap_err();
+        assert_eq!(err.code(), None);
+        assert!(err.to_vec()[0].starts_with("[E7] Error: "));
+        assert!(err.to_vec()[1].starts_with("[E8] Error: "));
+
+        // 包装合并后的错误, 仍不产生错误码, 且保留第一个 [E7] 前缀
+        let err = Err::<(), MoreError>(err).m(m!(fname)).unwrap_err();
+        assert_eq!(err.code(), None);
+        assert_eq!(err.depth(), 3);
+        assert!(err.to_vec()[1].starts_with("[E7] Error: "));
+        assert!(err.to_vec()[2].starts_with("[E8] Error: "));
+
+        // 只有一个时原样返回
+        let mut errors = MoreErrors::new();
+        errors.push(crate::m_code!(fname, "abc", 7));
+        assert_eq!(errors.into_result().unwrap_err().code(), Some(7));
+    }
+
+    #[test]
+    fn test_or_return_log() {
+        let fname = "test_or_return_log";
+
+        let half = |x: Result<i32, MoreError>| -> i32 { crate::ok_or_return_log!(x, -1) / 2 };
+        assert_eq!(half(Ok(4)), 2);
+        assert_eq!(half(m!(fname, "abc", "result")), -1);
+
+        let half = |x: Option<i32>| -> i32 { crate::some_or_return_log!(x, -1) / 2 };
+        assert_eq!(half(Some(4)), 2);
+        assert_eq!(half(None), -1);
+    }
+
+    #[test]
+    fn test_push() {
+        let fname = "test_push";
+        let mut err = m!(fname, "failed", "more");
+        for i in 1..=3 {
+            err.push(file!(), line!(), fname, format!("retry {}", i));
+        }
+
+        let frames = err.to_vec();
+        assert_eq!(frames.len(), 4);
+        assert!(frames[0].ends_with("test_push() failed"));
+        for (i, frame) in frames.iter().skip(1).enumerate() {
+            assert!(frame.starts_with("Error: src/more_error.rs:"));
+            assert!(frame.ends_with(&format!("test_push() retry {}", i + 1)));
+        }
+
+        let err = m!(fname, "failed", "more")
+            .push_m(m!(fname, "a"))
+            .push_m(m!(fname, "b"));
+        assert_eq!(err.depth(), 3);
+        assert!(err.to_string().ends_with("test_push() b"));
+    }
+
+    #[cfg(feature = "use_serde")]
+    #[test]
+    fn test_serde() {
+        let fname = "test_serde";
+        let inner: Result<(), io::Error> = Err(io::Error::new(io::ErrorKind::NotFound, "abc"));
+        let err = inner.m(m!(fname, "inner")).m(m!(fname, "outer")).unwrap_err();
+
+        let text = serde_json::to_string(&err).unwrap();
+        assert_eq!(text, serde_json::to_string(&err.to_string()).unwrap());
+
+        let err2: MoreError = serde_json::from_str(&text).unwrap();
+        assert_eq!(err2.to_string(), err.to_string());
+        assert_eq!(err2.depth(), 3);
+        assert!(err2.source().is_none());
+
+        let errs: Vec<MoreError> = serde_json::from_str("[\"a\",\"b\\nc\"]").unwrap();
+        assert_eq!(errs[1].to_vec(), ["b", "c"]);
+
+        // 错误码随 [E{code}] 前缀保留
+        let err = crate::m_code!(fname, "abc", 404);
+        let text = serde_json::to_string(&err).unwrap();
+        assert!(text.starts_with("\"[E404] Error: "));
+        let err2: MoreError = serde_json::from_str(&text).unwrap();
+        assert_eq!(err2.code(), Some(404));
+        assert_eq!(err2.to_string(), err.to_string());
+        assert_eq!(err2.depth(), 1);
+    }
+
+    #[test]
+    fn test_source() {
+        let fname = "test_source";
+        let inner: Result<(), io::Error> = Err(io::Error::new(io::ErrorKind::NotFound, "abc"));
+        let err = inner.ms(m!(fname, "inner")).ms(m!(fname, "outer")).unwrap_err();
+
+        // source 指向最初的 io::Error
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::NotFound);
+
+        // 嵌套 MoreError 的文本与之前一致
+        let lines = err.to_string().lines().map(String::from).collect::<Vec<String>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("Error: ") && lines[0].ends_with("test_source() outer"));
+        assert!(lines[1].starts_with("Error: ") && lines[1].ends_with("test_source() inner"));
+        assert!(lines[2].starts_with("Error: Custom"));
+
+        // 没有最初 Error 的 MoreError
+        assert!(m!(fname, "none", "more").source().is_none());
+        assert!(Err::<(), MoreError>(m!(fname, "none", "more"))
+            .m(m!(fname))
+            .unwrap_err()
+            .source()
+            .is_none());
+
+        // m() 不保留 source
+        let inner: Result<(), io::Error> = Err(io::Error::new(io::ErrorKind::NotFound, "abc"));
+        assert!(inner.m(m!(fname)).unwrap_err().source().is_none());
+    }
+
+    #[test]
+    fn test_source_not_required() {
+        let fname = "test_source_not_required";
+
+        // PoisonError<MutexGuard> 不是 Send, 也不是 'static
+        let mutex = std::sync::Mutex::new(1);
+        assert_eq!(*mutex.lock().m(m!(fname)).unwrap(), 1);
+
+        let _ = std::panic::catch_unwind(|| {
+            let _guard = mutex.lock().unwrap();
+            panic!("poison");
+        });
+        let err = mutex.lock().m(m!(fname, "inner")).m(m!(fname, "outer")).unwrap_err();
+        let lines = err.to_vec();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("test_source_not_required() outer"));
+        assert!(lines[1].ends_with("test_source_not_required() inner"));
+        assert!(lines[2].starts_with("Error: PoisonError"));
+    }
+
+    #[test]
+    fn test_text() {
+        let fname = "test_text";
+        let id = 5;
+
+        // &str、&String、String 均可
+        assert!(m!(fname, "id=5", "more").to_string().ends_with("test_text() id=5"));
+        assert!(m!(fname, &format!("id={}", id), "more")
+            .to_string()
+            .ends_with("test_text() id=5"));
+        assert!(m!(fname, format!("id={}", id), "more")
+            .to_string()
+            .ends_with("test_text() id=5"));
+
+        let result: Result<(), io::Error> = Err(io::Error::new(io::ErrorKind::NotFound, "abc"));
+        let text = result.m(m!(fname, format!("id={}", id))).unwrap_err().to_string();
+        assert!(text.lines().next().unwrap().ends_with("test_text() id=5"));
+
+        let result: Result<(), io::Error> = Err(io::Error::new(io::ErrorKind::NotFound, "abc"));
+        let text = result.as_m(m!(fname, String::from("id=5"))).unwrap_err().to_string();
+        assert!(text.ends_with("test_text() id=5"));
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let fname = "test_to_vec";
+        let mut result: Result<(), MoreError> = m!(fname, "0", "result");
+        for i in 1..5 {
+            result = result.m(m!(fname, format!("{}", i)));
+        }
+        let err = result.unwrap_err();
+
+        let frames = err.to_vec();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(err.depth(), 5);
+        assert!(frames[0].starts_with("Error: ") && frames[0].ends_with("test_to_vec() 4"));
+        assert!(frames[4].starts_with("Error: ") && frames[4].ends_with("test_to_vec() 0"));
+        assert_eq!(frames.join("\n"), err.to_string());
+
+        assert_eq!(m!(fname, "0", "more").depth(), 1);
+        assert_eq!(MoreError::message("abc").to_vec(), ["abc"]);
     }
 }
@@ -1,6 +1,6 @@
 use {
     crate::{datetime::*, use_m::*},
-    chrono::{DateTime, FixedOffset, NaiveDate, TimeZone},
+    chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone},
     mysql::{
         prelude::{ConvIr, FromValue},
         FromValueError, Value,
@@ -19,6 +19,9 @@ use {
 // 范围为 1000-01-01 00:00:00 至 9999-12-31 23:59:59
 // DateTime 不支持 .000Z, +08:00 等包含时区的字符串
 
+/// SqlDate::parse_flexible 依次尝试的格式
+const FLEXIBLE_DATE_FORMATS: [&str; 5] = ["%Y-%m-%d", "%Y/%m/%d", "%Y%m%d", "%d-%m-%Y", "%m/%d/%Y"];
+
 #[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
 pub struct SqlDate {
     /// 日期
@@ -29,6 +32,36 @@ pub struct SqlDate {
 }
 
 impl SqlDate {
+    /// 增加 n 个月, 超出目标月天数时取月末, 如 01-31 + 1 = 02-28/29
+    #[auto_func_name]
+    pub fn add_months(&self, n: i32) -> Result<Self, MoreError> {
+        let months = self.ndate.year() as i64 * 12 + self.ndate.month0() as i64 + n as i64;
+        let year = i32::try_from(months.div_euclid(12)).m(m!(fname))?;
+        let month = months.rem_euclid(12) as u32 + 1;
+        let day = self.ndate.day().min(bjtc_days_in_month(year, month));
+        match NaiveDate::from_ymd_opt(year, month, day) {
+            Some(ndate) => Ok(Self::new_n(ndate)),
+            None => m!(fname, format!("{} + {} months", self.sdate, n), "result"),
+        }
+    }
+
+    /// 从 unix 时间戳构造, 取北京时间所在日期
+    #[auto_func_name]
+    pub fn from_timestamp(ts: i64) -> Result<Self, MoreError> {
+        bjtc_nd(ts, 0).m(m!(fname)).map(Self::new_n)
+    }
+
+    /// 从 a 到 b 的完整月数, b 早于 a 时为负
+    pub fn months_between(a: &SqlDate, b: &SqlDate) -> i32 {
+        let mut months = (b.ndate.year() - a.ndate.year()) * 12 + b.ndate.month() as i32 - a.ndate.month() as i32;
+        match a.add_months(months) {
+            Ok(end) if months > 0 && end > *b => months -= 1,
+            Ok(end) if months < 0 && end < *b => months += 1,
+            _ => {}
+        }
+        months
+    }
+
     #[allow(dead_code)]
     #[inline]
     pub fn n(&self) -> &NaiveDate {
@@ -46,6 +79,14 @@ impl SqlDate {
         Self { ndate: date, sdate }
     }
 
+    /// 依次尝试常见格式解析, 用于导入表格等来源的数据
+    #[auto_func_name]
+    pub fn parse_flexible(text: &str) -> Result<Self, MoreError> {
+        bjtc_parse_date_flexible(text, &FLEXIBLE_DATE_FORMATS)
+            .m(m!(fname))
+            .map(Self::new_n)
+    }
+
     #[inline]
     pub fn s(&self) -> &String {
         &self.sdate
@@ -66,6 +107,27 @@ impl SqlDate {
 
         Ok(())
     }
+
+    /// 北京时间当天零点的 unix 时间戳
+    #[inline]
+    pub fn to_timestamp(&self) -> i64 {
+        bjtc_dn(&self.ndate)
+    }
+
+    /// 北京时间今天
+    pub fn today() -> Self {
+        Self::new_n(bj_date())
+    }
+
+    /// 北京时间明天
+    pub fn tomorrow() -> Self {
+        Self::new_n(bjtc_add_days(&bj_date(), 1))
+    }
+
+    /// 北京时间昨天
+    pub fn yesterday() -> Self {
+        Self::new_n(bjtc_sub_days(&bj_date(), 1))
+    }
 }
 
 impl fmt::Debug for SqlDate {
@@ -93,6 +155,25 @@ impl FromValue for SqlDate {
     type Intermediate = SqlDateParser;
 }
 
+impl Serialize for SqlDate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.sdate)
+    }
+}
+
+impl<'de> Deserialize<'de> for SqlDate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        SqlDate::new(s).map_err(serde::de::Error::custom)
+    }
+}
+
 pub struct SqlDateParser {
     value: Value,
     output: SqlDate,
@@ -142,6 +223,12 @@ pub struct SqlTime {
 }
 
 impl SqlTime {
+    /// 从 unix 时间戳构造
+    #[auto_func_name]
+    pub fn from_timestamp(ts: i64) -> Result<Self, MoreError> {
+        bjtc_nt(ts, 0).m(m!(fname)).map(Self::new_n)
+    }
+
     #[allow(dead_code)]
     #[inline]
     pub fn n(&self) -> &DateTime<FixedOffset> {
@@ -159,6 +246,11 @@ impl SqlTime {
         Self { ntime: time, stime }
     }
 
+    /// 北京时间当前时刻
+    pub fn now() -> Self {
+        Self::new_n(bj_time())
+    }
+
     #[inline]
     pub fn s(&self) -> &String {
         &self.stime
@@ -179,6 +271,18 @@ impl SqlTime {
 
         Ok(())
     }
+
+    /// unix 时间戳, 秒
+    #[inline]
+    pub fn to_timestamp(&self) -> i64 {
+        self.ntime.timestamp()
+    }
+
+    /// unix 时间戳, 毫秒
+    #[inline]
+    pub fn to_timestamp_millis(&self) -> i64 {
+        self.ntime.timestamp_millis()
+    }
 }
 
 impl fmt::Debug for SqlTime {
@@ -271,3 +375,95 @@ impl ConvIr<SqlTime> for SqlTimeParser {
         self.value
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_add_months() {
+        let date = SqlDate::new("2024-01-31".to_string()).unwrap();
+        assert_eq!(date.add_months(1).unwrap().s(), "2024-02-29");
+        assert_eq!(date.add_months(13).unwrap().s(), "2025-02-28");
+        assert_eq!(date.add_months(-2).unwrap().s(), "2023-11-30");
+        assert_eq!(date.add_months(-13).unwrap().s(), "2022-12-31");
+        assert_eq!(date.add_months(0).unwrap().s(), "2024-01-31");
+        assert!(date.add_months(i32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_now() {
+        let before = bj_timestamp();
+        let now = SqlTime::now();
+        assert!(now.n().timestamp() >= before);
+        assert!(now.n().timestamp() <= bj_timestamp());
+        assert_eq!(now.n().offset().local_minus_utc(), 8 * 3600);
+    }
+
+    #[test]
+    fn test_months_between() {
+        let d = |s: &str| SqlDate::new(s.to_string()).unwrap();
+        assert_eq!(SqlDate::months_between(&d("2024-01-31"), &d("2024-02-29")), 1);
+        assert_eq!(SqlDate::months_between(&d("2024-01-15"), &d("2024-02-14")), 0);
+        assert_eq!(SqlDate::months_between(&d("2023-11-15"), &d("2024-02-15")), 3);
+        assert_eq!(SqlDate::months_between(&d("2024-02-15"), &d("2023-11-15")), -3);
+        assert_eq!(SqlDate::months_between(&d("2024-02-14"), &d("2023-11-15")), -2);
+        assert_eq!(SqlDate::months_between(&d("2024-03-01"), &d("2024-03-01")), 0);
+    }
+
+    #[test]
+    fn test_parse_flexible() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        for text in ["2024-01-05", "2024/1/5", "20240105", "05-01-2024", "1/5/2024"] {
+            let sql_date = SqlDate::parse_flexible(text).unwrap();
+            assert_eq!(*sql_date.n(), date);
+            assert_eq!(sql_date.s(), "2024-01-05");
+        }
+
+        let err = SqlDate::parse_flexible("05-Jan-2024").unwrap_err();
+        assert!(err.to_string().contains("05-Jan-2024"));
+    }
+
+    #[test]
+    fn test_serde() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Row {
+            date: SqlDate,
+            time: SqlTime,
+        }
+
+        let row = Row {
+            date: SqlDate::new("2024-03-01".to_string()).unwrap(),
+            time: SqlTime::new("2024-03-01T12:34:56".to_string()).unwrap(),
+        };
+        let text = serde_json::to_string(&row).unwrap();
+        assert_eq!(text, r#"{"date":"2024-03-01","time":"2024-03-01T12:34:56"}"#);
+        assert_eq!(serde_json::from_str::<Row>(&text).unwrap(), row);
+
+        assert!(serde_json::from_str::<SqlDate>(r#""2024-13-01""#).is_err());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        // 2024-03-01 00:00:00 +08:00
+        let date = SqlDate::new("2024-03-01".to_string()).unwrap();
+        assert_eq!(date.to_timestamp(), 1709222400);
+        assert_eq!(SqlDate::from_timestamp(1709222400).unwrap(), date);
+        assert_eq!(SqlDate::from_timestamp(1709222399).unwrap().s(), "2024-02-29");
+
+        let time = SqlTime::from_timestamp(1709222400 + 3661).unwrap();
+        assert_eq!(time.n().to_rfc3339(), "2024-03-01T01:01:01+08:00");
+        assert_eq!(time.to_timestamp(), 1709222400 + 3661);
+        assert_eq!(time.to_timestamp_millis(), (1709222400 + 3661) * 1000);
+
+        assert!(SqlTime::from_timestamp(i64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_today() {
+        let today = SqlDate::today();
+        assert_eq!(*today.n(), bj_date());
+        assert_eq!(*SqlDate::tomorrow().n(), bjtc_add_days(today.n(), 1));
+        assert_eq!(*SqlDate::yesterday().n(), bjtc_sub_days(today.n(), 1));
+    }
+}
@@ -1,24 +1,148 @@
 use {
-    crate::use_m::*,
+    crate::{
+        sql_date::{SqlDate, SqlTime},
+        use_m::*,
+    },
     mysql::{
+        params,
         params::Params,
-        prelude::{FromRow, Queryable},
-        OptsBuilder, Pool, PooledConn,
+        prelude::{FromRow, FromValue, Queryable},
+        OptsBuilder, Pool, PooledConn, Row, Transaction, TxOpts, Value,
     },
     std::sync::MutexGuard,
 };
 
+/// create_batch 每条 INSERT 语句最多包含的记录数
+const DEFAULT_BATCH_SIZE: usize = 1000;
+
+#[auto_func_name]
+/// 按 fields_pi 的顺序把命名参数转为位置参数, 用于 create_batch
+fn batch_values(fields_pi: &str, params: Params, values: &mut Vec<Value>) -> Result<(), MoreError> {
+    match params {
+        Params::Named(mut map) => {
+            for name in fields_pi.split(", ") {
+                let name = name.trim_start_matches(':');
+                match map.remove(name) {
+                    Some(value) => values.push(value),
+                    None => return m!(fname, format!("缺少参数 {}", name), "result"),
+                }
+            }
+            Ok(())
+        }
+        Params::Empty => Ok(()),
+        Params::Positional(_) => m!(fname, "不支持位置参数", "result"),
+    }
+}
+
+/// 生成批量插入的 sql, 每条记录一组 (?, ?, ...)
+fn batch_sql(table_name: &str, fields_bi: &str, fields_pi: &str, count: usize) -> String {
+    let row = format!("({})", vec!["?"; fields_pi.split(", ").count()].join(", "));
+    format!(
+        "INSERT INTO {} ({}) VALUES {}",
+        table_name,
+        fields_bi,
+        vec![row; count].join(", ")
+    )
+}
+
+#[auto_func_name]
+/// 检查列名, 仅允许字母和下划线, 避免拼接 sql 时注入
+fn check_column(column: &str) -> Result<(), MoreError> {
+    if !column.is_empty() && column.chars().all(|ch| ch.is_ascii_alphabetic() || ch == '_') {
+        Ok(())
+    } else {
+        m!(fname, &format!("无效的列名 {}", column), "result")
+    }
+}
+
+#[auto_func_name]
+/// 在 conn 上执行事务, f 返回 Ok 时 commit, 返回 Err 时 rollback, panic 时由 Transaction 的 drop 自动 rollback
+///
+/// rollback 失败时仍返回 f 的 Error, rollback 的 Error 追加在末尾
+fn run_transaction<F, T>(conn: &mut PooledConn, f: F) -> Result<T, MoreError>
+where
+    F: FnOnce(&mut Transaction) -> Result<T, MoreError>,
+{
+    let mut tx = conn.start_transaction(TxOpts::default()).m(m!(fname))?;
+    match f(&mut tx) {
+        Ok(value) => {
+            tx.commit().m(m!(fname))?;
+            Ok(value)
+        }
+        Err(err) => {
+            let rollback = tx.rollback();
+            Err(err).m(m!(fname)).map_err(|err| match rollback {
+                Ok(()) => err,
+                Err(rollback_err) => err.push_m(m!(fname, format!("rollback: {:?}", rollback_err))),
+            })
+        }
+    }
+}
+
 /// 负责通过 lazy_static 创建 DbPool 的类
 pub trait CreateDbPool {
     /// 返回加锁的 DbPool, 注意: args 无效时应返回 Error
     fn lock() -> Result<MutexGuard<'static, DbPool>, MoreError>;
 }
 
+/// DbPool 执行 sql 所用的连接, 默认由 Pool 提供, 测试时可以由 new_with_factory 注入其它实现
+pub trait GetConn: Send {
+    /// 执行 sql, 不关心结果
+    fn exec_drop(&mut self, sql: &str, params: &Params) -> Result<(), MoreError>;
+
+    /// 执行 sql, 返回可能的第一行
+    fn exec_first_opt(&mut self, sql: &str, params: &Params) -> Result<Option<Row>, MoreError>;
+
+    /// 执行 sql, 返回 last_insert_id
+    fn exec_iter(&mut self, sql: &str, params: &Params) -> Result<Option<u64>, MoreError>;
+
+    /// 执行 sql, 返回全部行
+    fn exec_opt(&mut self, sql: &str, params: &Params) -> Result<Vec<Row>, MoreError>;
+}
+
+impl GetConn for Pool {
+    #[auto_func_name]
+    fn exec_drop(&mut self, sql: &str, params: &Params) -> Result<(), MoreError> {
+        self.get_conn().m(m!(fname))?.exec_drop(sql, params).m(m!(fname))
+    }
+
+    #[auto_func_name]
+    fn exec_first_opt(&mut self, sql: &str, params: &Params) -> Result<Option<Row>, MoreError> {
+        self.get_conn().m(m!(fname))?.exec_first(sql, params).m(m!(fname))
+    }
+
+    #[auto_func_name]
+    fn exec_iter(&mut self, sql: &str, params: &Params) -> Result<Option<u64>, MoreError> {
+        self.get_conn()
+            .m(m!(fname))?
+            .exec_iter(sql, params)
+            .map(|x| x.last_insert_id())
+            .m(m!(fname))
+    }
+
+    #[auto_func_name]
+    fn exec_opt(&mut self, sql: &str, params: &Params) -> Result<Vec<Row>, MoreError> {
+        self.get_conn().m(m!(fname))?.exec(sql, params).m(m!(fname))
+    }
+}
+
+/// new_with_factory 时没有 args, 使用空 args
+static EMPTY_ARGS: DbPoolArgs = DbPoolArgs {
+    ip_or_hostname: String::new(),
+    port: 0,
+    user: String::new(),
+    password: String::new(),
+    db_name: String::new(),
+};
+
 #[cfg(feature = "use_sql")]
 /// 全局数据库连接池
 pub struct DbPool {
     pool: Option<Pool>,
     args: &'static DbPoolArgs,
+
+    /// new_with_factory 注入的连接, 有值时不使用 pool
+    factory: Option<Box<dyn GetConn>>,
 }
 
 #[cfg(feature = "use_sql")]
@@ -29,6 +153,7 @@ impl DbPool {
         match &self.pool {
             Some(pool) => Ok(pool.clone()),
             None => {
+                self.args.validate().m(m!(fname))?;
                 let opts = OptsBuilder::new()
                     .ip_or_hostname(Some(&self.args.ip_or_hostname))
                     .tcp_port(self.args.port)
@@ -44,21 +169,60 @@ impl DbPool {
     }
 
     #[auto_func_name]
-    /// 获取可用连接
+    /// 获取可用连接, 用于事务, 注入的连接不是 PooledConn, 不支持
     fn _get(&mut self) -> Result<PooledConn, MoreError> {
+        if self.factory.is_some() {
+            return m!(fname, "new_with_factory 注入的连接不支持事务", "result");
+        }
         self._create().m(m!(fname))?.get_conn().m(m!(fname))
     }
 
+    #[auto_func_name]
+    /// 在可用连接上执行 f, 有注入的连接时使用它
+    fn _with_conn<F, T>(&mut self, f: F) -> Result<T, MoreError>
+    where
+        F: FnOnce(&mut dyn GetConn) -> Result<T, MoreError>,
+    {
+        match &mut self.factory {
+            Some(factory) => f(factory.as_mut()),
+            None => f(&mut self._create().m(m!(fname))?),
+        }
+    }
+
+    #[auto_func_name]
+    /// 执行 sql, 返回第一行第一列, 没有结果时返回 None
+    fn exec_scalar<T>(&mut self, sql: &str, params: Params) -> Result<Option<T>, MoreError>
+    where
+        Self: 'static,
+        T: FromValue,
+    {
+        match self
+            ._with_conn(|conn| conn.exec_first_opt(sql, &params))
+            .f(m!(fname, || { format!("{}: {:?}", sql, &params) }))?
+            .map(mysql::from_row_opt::<T>)
+        {
+            Some(Ok(value)) => Ok(Some(value)),
+            Some(Err(err)) => Err(err).m(m!(fname)),
+            None => Ok(None),
+        }
+    }
+
+    #[auto_func_name]
+    /// 执行 COUNT(*) 等 sql, 返回第一行第一列, 没有结果时返回 0
+    fn get_count(&mut self, sql: &str, params: Params) -> Result<i64, MoreError>
+    where
+        Self: 'static,
+    {
+        Ok(self.exec_scalar::<i64>(sql, params).m(m!(fname))?.unwrap_or(0))
+    }
+
     #[auto_func_name]
     /// 执行 sql, 返回 id
     fn get_id(&mut self, sql: &str, params: Params) -> Result<Option<u64>, MoreError>
     where
         Self: 'static,
     {
-        self._get()
-            .m(m!(fname))?
-            .exec_iter(sql, &params)
-            .map(|x| x.last_insert_id())
+        self._with_conn(|conn| conn.exec_iter(sql, &params))
             .f(m!(fname, || { format!("{} {:?}", sql, params) }))
     }
 
@@ -68,14 +232,44 @@ impl DbPool {
     where
         Self: 'static,
     {
-        self._get()
-            .m(m!(fname))?
-            .exec_drop(sql, &params)
+        self._with_conn(|conn| conn.exec_drop(sql, &params))
             .f(m!(fname, || { format!("{}: {:?}", sql, &params) }))
     }
 
     pub fn new(args: &'static DbPoolArgs) -> Self {
-        Self { pool: None, args }
+        Self {
+            pool: None,
+            args,
+            factory: None,
+        }
+    }
+
+    /// 由 factory 执行 sql, 不连接 args 指定的数据库, 用于测试, 不支持事务
+    pub fn new_with_factory<F>(factory: F) -> Self
+    where
+        F: GetConn + 'static,
+    {
+        Self {
+            pool: None,
+            args: &EMPTY_ARGS,
+            factory: Some(Box::new(factory)),
+        }
+    }
+
+    #[auto_func_name]
+    /// 检查能否连接数据库并执行 sql
+    pub fn ping(&mut self) -> Result<(), MoreError> {
+        self.exec_scalar::<i32>("SELECT 1", Params::Empty).m(m!(fname))?;
+        Ok(())
+    }
+
+    #[auto_func_name]
+    /// 执行事务, 见 run_transaction
+    pub fn transaction<F, T>(&mut self, f: F) -> Result<T, MoreError>
+    where
+        F: FnOnce(&mut Transaction) -> Result<T, MoreError>,
+    {
+        run_transaction(&mut self._get().m(m!(fname))?, f).m(m!(fname))
     }
 }
 
@@ -89,6 +283,32 @@ pub struct DbPoolArgs {
     pub db_name: String,        // 数据库
 }
 
+impl DbPoolArgs {
+    #[auto_func_name]
+    /// 检查参数, 地址、用户、数据库不能为空, 端口不能为 0
+    pub fn validate(&self) -> Result<(), MoreError> {
+        let mut invalid = Vec::new();
+        if self.ip_or_hostname.is_empty() {
+            invalid.push("ip_or_hostname");
+        }
+        if self.port == 0 {
+            invalid.push("port");
+        }
+        if self.user.is_empty() {
+            invalid.push("user");
+        }
+        if self.db_name.is_empty() {
+            invalid.push("db_name");
+        }
+
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            m!(fname, format!("无效的参数 {}", invalid.join(", ")), "result")
+        }
+    }
+}
+
 pub trait SqlModel {
     //
     // 从这里开始是需要 trait 实现的, 在 AsSqlModel 宏实现
@@ -152,6 +372,36 @@ pub trait SqlModel {
             .m(m!(fname))
     }
 
+    #[auto_func_name]
+    /// 批量增, 每 DEFAULT_BATCH_SIZE 条记录一条 INSERT 语句
+    ///
+    /// 注意: MySQL 对批量插入只返回第一条记录的自增 id, 因此返回的是每条 INSERT 语句的第一个 id, 而不是每条记录的 id
+    fn create_batch(rows: &[Self]) -> Result<Vec<Option<u64>>, MoreError>
+    where
+        Self: 'static + Sized,
+    {
+        let mut ids = Vec::new();
+        for chunk in rows.chunks(DEFAULT_BATCH_SIZE) {
+            let mut values = Vec::new();
+            for row in chunk {
+                batch_values(Self::make_fields_pi(), row.make_fields_vi(), &mut values).m(m!(fname))?;
+            }
+            let sql = batch_sql(
+                Self::table_name(),
+                Self::make_fields_bi(),
+                Self::make_fields_pi(),
+                chunk.len(),
+            );
+            ids.push(
+                Self::lock()
+                    .m(m!(fname))?
+                    .get_id(&sql, Params::Positional(values))
+                    .m(m!(fname))?,
+            );
+        }
+        Ok(ids)
+    }
+
     #[auto_func_name]
     /// 删
     fn delete(condition: &str, params: Params) -> Result<(), MoreError> {
@@ -164,6 +414,76 @@ pub trait SqlModel {
             .m(m!(fname))
     }
 
+    #[auto_func_name]
+    /// 按 id 删
+    fn delete_by_id(id: u64) -> Result<(), MoreError> {
+        Self::delete("id=:id", params! { "id" => id }).m(m!(fname))
+    }
+
+    #[auto_func_name]
+    /// 是否存在满足条件的记录, 不获取记录内容
+    fn exists(condition: &str, params: Params) -> Result<bool, MoreError>
+    where
+        Self: 'static,
+    {
+        Ok(Self::lock()
+            .m(m!(fname))?
+            .exec_scalar::<i32>(
+                &format!("SELECT 1 FROM {} WHERE {} LIMIT 1", Self::table_name(), condition),
+                params,
+            )
+            .m(m!(fname))?
+            .is_some())
+    }
+
+    #[auto_func_name]
+    /// 获取 column 在 [from, to] 日期范围内的多个记录
+    fn find_between_dates(column: &str, from: &SqlDate, to: &SqlDate) -> Result<Vec<Self>, MoreError>
+    where
+        Self: 'static + Sized + FromRow,
+    {
+        check_column(column).m(m!(fname))?;
+        Self::select_some(
+            &format!("WHERE `{}` BETWEEN :from AND :to", column),
+            params! { "from" => from.clone(), "to" => to.clone() },
+        )
+        .m(m!(fname))
+    }
+
+    #[auto_func_name]
+    /// 获取 column 在 [from, to] 时间范围内的多个记录
+    fn find_between_times(column: &str, from: &SqlTime, to: &SqlTime) -> Result<Vec<Self>, MoreError>
+    where
+        Self: 'static + Sized + FromRow,
+    {
+        check_column(column).m(m!(fname))?;
+        Self::select_some(
+            &format!("WHERE `{}` BETWEEN :from AND :to", column),
+            params! { "from" => from.clone(), "to" => to.clone() },
+        )
+        .m(m!(fname))
+    }
+
+    #[auto_func_name]
+    /// 获取 column 最小的单个记录
+    fn find_earliest(column: &str) -> Result<Option<Self>, MoreError>
+    where
+        Self: 'static + Sized + FromRow,
+    {
+        check_column(column).m(m!(fname))?;
+        Self::select_one(&format!("ORDER BY `{}` ASC LIMIT 1", column), Params::Empty).m(m!(fname))
+    }
+
+    #[auto_func_name]
+    /// 获取 column 最大的单个记录
+    fn find_latest(column: &str) -> Result<Option<Self>, MoreError>
+    where
+        Self: 'static + Sized + FromRow,
+    {
+        check_column(column).m(m!(fname))?;
+        Self::select_one(&format!("ORDER BY `{}` DESC LIMIT 1", column), Params::Empty).m(m!(fname))
+    }
+
     #[auto_func_name]
     /// 获取可能的单个记录, 含带参条件
     fn get_row<T>(sql: &str, params: Params) -> Result<Option<T>, MoreError>
@@ -173,10 +493,9 @@ pub trait SqlModel {
     {
         match Self::lock()
             .m(m!(fname))?
-            ._get()
-            .m(m!(fname))?
-            .exec_first_opt(sql, &params)
+            ._with_conn(|conn| conn.exec_first_opt(sql, &params))
             .f(m!(fname, || { format!("{}: {:?}", sql, &params) }))?
+            .map(T::from_row_opt)
         {
             Some(Ok(row)) => Ok(Some(row)),
             Some(Err(err)) => Err(err).m(m!(fname)),
@@ -194,15 +513,16 @@ pub trait SqlModel {
         // 全部结果
         let rows = Self::lock()
             .m(m!(fname))?
-            ._get()
-            .m(m!(fname))?
-            .exec_opt(sql, &params)
-            .f(m!(fname, || { format!("{}: {:?}", sql, &params) }))?;
+            ._with_conn(|conn| conn.exec_opt(sql, &params))
+            .f(m!(fname, || { format!("{}: {:?}", sql, &params) }))?
+            .into_iter()
+            .map(T::from_row_opt)
+            .collect::<Vec<_>>();
 
         // 如果有 FromRowError, 抛出异常, 这样后续可以 unwrap (map 中不可抛出异常)
         for (i, row) in rows.iter().enumerate() {
             if let Err(err) = row {
-                return Err(err).f(m!(fname, || { format!("{}: {:?}, row[{}]", sql, &params, i) }));
+                return err.f(m!(fname, || { format!("{}: {:?}, row[{}]", sql, &params, i) }));
             }
         }
 
@@ -210,6 +530,25 @@ pub trait SqlModel {
         Ok(rows.into_iter().map(|x| x.unwrap()).collect())
     }
 
+    #[auto_func_name]
+    /// 执行任意 sql, 比如 SHOW / CALL, 返回多个记录, 同 get_rows
+    fn raw_exec<T>(sql: &str, params: Params) -> Result<Vec<T>, MoreError>
+    where
+        Self: 'static,
+        T: FromRow,
+    {
+        Self::get_rows(sql, params).m(m!(fname))
+    }
+
+    #[auto_func_name]
+    /// 执行任意 sql, 比如 DDL, 不关心结果
+    fn raw_exec_drop(sql: &str, params: Params) -> Result<(), MoreError>
+    where
+        Self: 'static,
+    {
+        Self::lock().m(m!(fname))?.get_nothing(sql, params).m(m!(fname))
+    }
+
     #[auto_func_name]
     fn save_as(&self, id: u32) -> Result<(), MoreError> {
         Self::lock()
@@ -226,26 +565,47 @@ pub trait SqlModel {
             .m(m!(fname))
     }
 
+    #[auto_func_name]
+    /// 按 id 获取可能的单个记录
+    fn select_by_id(id: u64) -> Result<Option<Self>, MoreError>
+    where
+        Self: 'static + Sized + FromRow,
+    {
+        Self::select_one("WHERE id=:id", params! { "id" => id }).m(m!(fname))
+    }
+
+    #[auto_func_name]
+    /// 获取记录数, 含带参条件
+    fn select_count(where_sql: &str, params: Params) -> Result<i64, MoreError>
+    where
+        Self: 'static,
+    {
+        Self::lock()
+            .m(m!(fname))?
+            .get_count(
+                &format!("SELECT COUNT(*) FROM {} {}", Self::table_name(), where_sql),
+                params,
+            )
+            .m(m!(fname))
+    }
+
     #[auto_func_name]
     /// 获取可能的单个记录, 含带参条件
     fn select_one(where_sql: &str, params: Params) -> Result<Option<Self>, MoreError>
     where
         Self: 'static + Sized + FromRow,
     {
+        let sql = format!(
+            "SELECT {} FROM {} {}",
+            Self::make_fields_b(),
+            Self::table_name(),
+            where_sql
+        );
         match Self::lock()
             .m(m!(fname))?
-            ._get()
-            .m(m!(fname))?
-            .exec_first_opt(
-                &format!(
-                    "SELECT {} FROM {} {}",
-                    Self::make_fields_b(),
-                    Self::table_name(),
-                    where_sql
-                ),
-                &params,
-            )
+            ._with_conn(|conn| conn.exec_first_opt(&sql, &params))
             .f(m!(fname, || { format!("{}: {:?}", where_sql, &params) }))?
+            .map(Self::from_row_opt)
         {
             Some(Ok(row)) => Ok(Some(row)),
             Some(Err(err)) => Err(err).m(m!(fname)),
@@ -253,6 +613,45 @@ pub trait SqlModel {
         }
     }
 
+    #[auto_func_name]
+    /// 获取可能的单个记录, 含带参条件, 没有时返回 default
+    fn select_one_or(default: Self, where_sql: &str, params: Params) -> Result<Self, MoreError>
+    where
+        Self: 'static + Sized + FromRow,
+    {
+        Ok(Self::select_one(where_sql, params).m(m!(fname))?.unwrap_or(default))
+    }
+
+    #[auto_func_name]
+    /// 分页获取记录, page 从 0 开始, 返回 (本页记录, 总记录数), 含带参条件
+    fn select_page(
+        where_sql: &str,
+        params: Params,
+        page: usize,
+        page_size: usize,
+    ) -> Result<(Vec<Self>, usize), MoreError>
+    where
+        Self: 'static + Sized + FromRow,
+    {
+        let total = Self::select_count(where_sql, params.clone()).m(m!(fname))?;
+        let rows = Self::select_some(
+            &format!("{} LIMIT {}, {}", where_sql, page.saturating_mul(page_size), page_size),
+            params,
+        )
+        .m(m!(fname))?;
+        Ok((rows, total as usize))
+    }
+
+    #[auto_func_name]
+    /// 获取单个值, 比如 SUM / MAX, 含带参条件
+    fn select_scalar<T>(sql: &str, params: Params) -> Result<Option<T>, MoreError>
+    where
+        Self: 'static,
+        T: FromValue,
+    {
+        Self::lock().m(m!(fname))?.exec_scalar(sql, params).m(m!(fname))
+    }
+
     #[auto_func_name]
     /// 获取多个记录, 含带参条件
     fn select_some(where_sql: &str, params: Params) -> Result<Vec<Self>, MoreError>
@@ -260,25 +659,24 @@ pub trait SqlModel {
         Self: 'static + Sized + FromRow,
     {
         // 全部结果
+        let sql = format!(
+            "SELECT {} FROM {} {}",
+            Self::make_fields_b(),
+            Self::table_name(),
+            where_sql
+        );
         let rows = Self::lock()
             .m(m!(fname))?
-            ._get()
-            .m(m!(fname))?
-            .exec_opt(
-                &format!(
-                    "SELECT {} FROM {} {}",
-                    Self::make_fields_b(),
-                    Self::table_name(),
-                    where_sql
-                ),
-                &params,
-            )
-            .f(m!(fname, || { format!("{}: {:?}", where_sql, &params) }))?;
+            ._with_conn(|conn| conn.exec_opt(&sql, &params))
+            .f(m!(fname, || { format!("{}: {:?}", where_sql, &params) }))?
+            .into_iter()
+            .map(Self::from_row_opt)
+            .collect::<Vec<_>>();
 
         // 如果有 FromRowError, 抛出异常, 这样后续可以 unwrap (map 中不可抛出异常)
         for (i, row) in rows.iter().enumerate() {
             if let Err(err) = row {
-                return Err(err).f(m!(fname, || { format!("{}: {:?}, row[{}]", where_sql, &params, i) }));
+                return err.f(m!(fname, || { format!("{}: {:?}, row[{}]", where_sql, &params, i) }));
             }
         }
 
@@ -286,6 +684,17 @@ pub trait SqlModel {
         Ok(rows.into_iter().map(|x| x.unwrap()).collect())
     }
 
+    #[auto_func_name]
+    /// 执行事务, 见 run_transaction, 获取连接后即释放 DbPool 锁, f 内可以调用其它 SqlModel 方法, 但它们不在事务内
+    fn with_transaction<F, T>(f: F) -> Result<T, MoreError>
+    where
+        Self: 'static,
+        F: FnOnce(&mut Transaction) -> Result<T, MoreError>,
+    {
+        let mut conn = Self::lock().m(m!(fname))?._get().m(m!(fname))?;
+        run_transaction(&mut conn, f).m(m!(fname))
+    }
+
     #[auto_func_name]
     /// 改
     fn update(fields_ei: &str, condition: &str, params: Params) -> Result<(), MoreError> {
@@ -297,4 +706,635 @@ pub trait SqlModel {
             )
             .m(m!(fname))
     }
+
+    #[auto_func_name]
+    /// 增, 唯一键冲突时改为更新, 返回值同 create
+    fn upsert(&self) -> Result<Option<u64>, MoreError> {
+        Self::lock()
+            .m(m!(fname))?
+            .get_id(
+                &format!(
+                    "INSERT INTO {} ({}) VALUES ({}) ON DUPLICATE KEY UPDATE {}",
+                    Self::table_name(),
+                    Self::make_fields_bi(),
+                    Self::make_fields_pi(),
+                    Self::make_fields_ei(),
+                ),
+                self.make_fields_vi(),
+            )
+            .m(m!(fname))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_batch_sql() {
+        assert_eq!(
+            batch_sql("abc", "`a`, `b`", ":a, :b", 3),
+            "INSERT INTO abc (`a`, `b`) VALUES (?, ?), (?, ?), (?, ?)"
+        );
+        assert_eq!(batch_sql("abc", "`a`", ":a", 1), "INSERT INTO abc (`a`) VALUES (?)");
+    }
+
+    #[test]
+    fn test_batch_values() {
+        let mut values = Vec::new();
+        batch_values(":a, :b", params! { "b" => 2, "a" => "x" }, &mut values).unwrap();
+        batch_values(":a, :b", params! { "a" => "y", "b" => 3 }, &mut values).unwrap();
+        assert_eq!(
+            values,
+            [Value::from("x"), Value::from(2), Value::from("y"), Value::from(3)]
+        );
+
+        assert!(batch_values(":a, :b", params! { "a" => 1 }, &mut values).is_err());
+        assert!(batch_values(":a", Params::Positional(vec![Value::from(1)]), &mut values).is_err());
+    }
+
+    #[test]
+    fn test_check_column() {
+        assert!(check_column("created_at").is_ok());
+        assert!(check_column("Date").is_ok());
+        assert!(check_column("").is_err());
+        assert!(check_column("id1").is_err());
+        assert!(check_column("a` OR 1=1").is_err());
+    }
+
+    lazy_static::lazy_static! {
+        static ref FAKE_POOL: std::sync::Mutex<DbPool> = std::sync::Mutex::new(DbPool::new_with_factory(FakeConn));
+
+        /// FakeConn 执行过的 sql
+        static ref FAKE_SQL: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+        /// 使用 FakeConn 的测试依次执行, 避免 FAKE_SQL 混杂
+        static ref FAKE_TEST: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    }
+
+    /// 执行 f, 返回期间 FakeConn 执行过的 sql
+    fn fake_sql<F>(f: F) -> Vec<String>
+    where
+        F: FnOnce(),
+    {
+        let _guard = FAKE_TEST.lock().unwrap_or_else(|err| err.into_inner());
+        FAKE_SQL.lock().unwrap().clear();
+        f();
+        std::mem::take(&mut *FAKE_SQL.lock().unwrap())
+    }
+
+    /// 不连接 MySQL, 记录 sql, 总是返回同一行 (1, "a")
+    struct FakeConn;
+
+    impl FakeConn {
+        fn log(sql: &str) {
+            FAKE_SQL.lock().unwrap().push(sql.to_string());
+        }
+
+        fn row() -> Row {
+            let columns = ["id", "k"]
+                .iter()
+                .map(|name| {
+                    // 列定义: catalog, schema, table, org_table, name, org_name, 定长部分
+                    let mut payload = b"\x03def\x00\x00\x00".to_vec();
+                    payload.push(name.len() as u8);
+                    payload.extend(name.as_bytes());
+                    payload.extend([0, 0x0c, 0x21, 0, 0, 0, 0, 0, 0xfd, 0, 0, 0]);
+                    mysql_common::packets::column_from_payload(payload).unwrap()
+                })
+                .collect::<Vec<_>>();
+            mysql_common::row::new_row(vec![Value::from(1u64), Value::from("a")], columns.into())
+        }
+    }
+
+    impl GetConn for FakeConn {
+        fn exec_drop(&mut self, sql: &str, _params: &Params) -> Result<(), MoreError> {
+            Self::log(sql);
+            Ok(())
+        }
+
+        fn exec_first_opt(&mut self, sql: &str, _params: &Params) -> Result<Option<Row>, MoreError> {
+            Self::log(sql);
+            Ok(Some(Self::row()))
+        }
+
+        fn exec_iter(&mut self, sql: &str, _params: &Params) -> Result<Option<u64>, MoreError> {
+            Self::log(sql);
+            Ok(Some(1))
+        }
+
+        fn exec_opt(&mut self, sql: &str, _params: &Params) -> Result<Vec<Row>, MoreError> {
+            Self::log(sql);
+            Ok(vec![Self::row(), Self::row()])
+        }
+    }
+
+    /// 使用 FakeConn 的 SqlModel
+    #[derive(Debug, PartialEq)]
+    struct FakeRow {
+        id: u64,
+        k: String,
+    }
+
+    impl FromRow for FakeRow {
+        fn from_row_opt(row: Row) -> Result<Self, mysql::FromRowError> {
+            let (id, k) = mysql::from_row_opt(row)?;
+            Ok(Self { id, k })
+        }
+    }
+
+    impl SqlModel for FakeRow {
+        fn equal(&self, other: &Self) -> bool {
+            self == other
+        }
+
+        fn equal_without_id(&self, other: &Self) -> bool {
+            self.k == other.k
+        }
+
+        fn lock() -> Result<MutexGuard<'static, DbPool>, MoreError> {
+            Ok(FAKE_POOL.lock().unwrap_or_else(|err| err.into_inner()))
+        }
+
+        fn make_create_table() -> &'static str {
+            ""
+        }
+
+        fn make_fields_b() -> &'static str {
+            "`id`, `k`"
+        }
+
+        fn make_fields_bi() -> &'static str {
+            "`k`"
+        }
+
+        fn make_fields_e() -> &'static str {
+            "id=:id, k=:k"
+        }
+
+        fn make_fields_ei() -> &'static str {
+            "k=:k"
+        }
+
+        fn make_fields_p() -> &'static str {
+            ":id, :k"
+        }
+
+        fn make_fields_pi() -> &'static str {
+            ":k"
+        }
+
+        fn make_fields_q() -> &'static str {
+            "\"id\", \"k\""
+        }
+
+        fn make_fields_qc() -> &'static str {
+            "\"id\", \"k\","
+        }
+
+        fn make_fields_v(&self) -> Params {
+            params! { "id" => self.id, "k" => &self.k }
+        }
+
+        fn make_fields_vi(&self) -> Params {
+            params! { "k" => &self.k }
+        }
+
+        fn table_name() -> &'static str {
+            "fake_row"
+        }
+    }
+
+    #[test]
+    fn test_find_between() {
+        let sql = fake_sql(|| {
+            let (from, to) = (SqlDate::default(), SqlDate::default());
+            assert_eq!(FakeRow::find_between_dates("created_at", &from, &to).unwrap().len(), 2);
+            let (from, to) = (SqlTime::default(), SqlTime::default());
+            assert_eq!(FakeRow::find_between_times("updated_at", &from, &to).unwrap().len(), 2);
+        });
+        assert_eq!(
+            sql,
+            [
+                "SELECT `id`, `k` FROM fake_row WHERE `created_at` BETWEEN :from AND :to",
+                "SELECT `id`, `k` FROM fake_row WHERE `updated_at` BETWEEN :from AND :to",
+            ]
+        );
+
+        // 无效的列名, 不执行 sql
+        let sql = fake_sql(|| {
+            let (from, to) = (SqlDate::default(), SqlDate::default());
+            let err = FakeRow::find_between_dates("a` OR 1=1", &from, &to).unwrap_err();
+            assert!(err.to_string().contains("无效的列名"));
+            let (from, to) = (SqlTime::default(), SqlTime::default());
+            assert!(FakeRow::find_between_times("", &from, &to).is_err());
+        });
+        assert!(sql.is_empty());
+    }
+
+    #[test]
+    fn test_find_earliest_latest() {
+        let expected = FakeRow {
+            id: 1,
+            k: "a".to_string(),
+        };
+
+        let sql = fake_sql(|| {
+            assert_eq!(FakeRow::find_earliest("created_at").unwrap(), Some(expected));
+            assert!(FakeRow::find_latest("created_at").unwrap().is_some());
+        });
+        assert_eq!(
+            sql,
+            [
+                "SELECT `id`, `k` FROM fake_row ORDER BY `created_at` ASC LIMIT 1",
+                "SELECT `id`, `k` FROM fake_row ORDER BY `created_at` DESC LIMIT 1",
+            ]
+        );
+
+        // 无效的列名, 不执行 sql
+        let sql = fake_sql(|| {
+            let err = FakeRow::find_earliest("a` OR 1=1").unwrap_err();
+            assert!(err.to_string().contains("无效的列名"));
+            let err = FakeRow::find_latest("id; DROP TABLE fake_row").unwrap_err();
+            assert!(err.to_string().contains("无效的列名"));
+        });
+        assert!(sql.is_empty());
+    }
+
+    #[test]
+    fn test_new_with_factory() {
+        let expected = FakeRow {
+            id: 1,
+            k: "a".to_string(),
+        };
+
+        let sql = fake_sql(|| {
+            let row = FakeRow::select_one("WHERE `k`=:k", params! { "k" => "a" })
+                .unwrap()
+                .unwrap();
+            assert_eq!(row, expected);
+            assert_eq!(FakeRow::select_some("", Params::Empty).unwrap().len(), 2);
+            // 两列不能转为单个值
+            assert!(FakeRow::select_scalar::<u64>("SELECT 1", Params::Empty).is_err());
+            assert_eq!(row.create().unwrap(), Some(1));
+            FakeRow::delete_by_id(1).unwrap();
+        });
+        assert_eq!(
+            sql,
+            [
+                "SELECT `id`, `k` FROM fake_row WHERE `k`=:k",
+                "SELECT `id`, `k` FROM fake_row ",
+                "SELECT 1",
+                "INSERT INTO fake_row (`k`) VALUES (:k)",
+                "DELETE FROM fake_row WHERE id=:id",
+            ]
+        );
+
+        // 注入的连接不支持事务
+        let err = FakeRow::with_transaction(|_| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("不支持事务"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let err = DbPoolArgs::default().validate().unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("无效的参数 ip_or_hostname, port, user, db_name"));
+
+        let mut args = DbPoolArgs {
+            ip_or_hostname: "127.0.0.1".to_string(),
+            port: 3306,
+            user: "root".to_string(),
+            password: String::new(),
+            db_name: "test".to_string(),
+        };
+        assert!(args.validate().is_ok());
+        args.port = 0;
+        assert!(args.validate().unwrap_err().to_string().ends_with("无效的参数 port"));
+
+        // 参数无效时, 不尝试连接
+        let err = DbPool::new(&EMPTY_ARGS).ping().unwrap_err();
+        assert!(err.to_string().contains("无效的参数"));
+    }
+
+    /// 需要 MySQL, 地址等由环境变量 PYTHON_COMM_DB_{HOST,PORT,USER,PASSWORD,NAME} 指定
+    ///
+    /// 使用它的测试默认忽略, 运行: cargo test --features integration_test -- --ignored
+    #[cfg(feature = "integration_test")]
+    fn test_pool() -> DbPool {
+        use std::env;
+
+        let args = Box::leak(Box::new(DbPoolArgs {
+            ip_or_hostname: env::var("PYTHON_COMM_DB_HOST").unwrap_or("127.0.0.1".to_string()),
+            port: env::var("PYTHON_COMM_DB_PORT").map_or(3306, |port| port.parse().unwrap()),
+            user: env::var("PYTHON_COMM_DB_USER").unwrap_or("root".to_string()),
+            password: env::var("PYTHON_COMM_DB_PASSWORD").unwrap_or_default(),
+            db_name: env::var("PYTHON_COMM_DB_NAME").unwrap_or("test".to_string()),
+        }));
+        DbPool::new(args)
+    }
+
+    #[cfg(feature = "integration_test")]
+    lazy_static::lazy_static! {
+        static ref TEST_POOL: std::sync::Mutex<DbPool> = std::sync::Mutex::new(test_pool());
+
+        /// 使用 python_comm_test_row 表的测试须串行
+        static ref TEST_TABLE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    }
+
+    /// 测试用的 SqlModel, 与 AsSqlModel 生成的代码相同
+    #[cfg(feature = "integration_test")]
+    #[derive(Debug, PartialEq)]
+    struct TestRow {
+        id: u64,
+        k: String,
+        v: i32,
+    }
+
+    #[cfg(feature = "integration_test")]
+    impl TestRow {
+        /// 加锁并重建空表
+        fn reset() -> MutexGuard<'static, ()> {
+            let guard = TEST_TABLE.lock().unwrap_or_else(|err| err.into_inner());
+            let mut pool = Self::lock().unwrap();
+            pool.get_nothing("DROP TABLE IF EXISTS python_comm_test_row", Params::Empty)
+                .unwrap();
+            pool.get_nothing(Self::make_create_table(), Params::Empty).unwrap();
+            guard
+        }
+
+        fn new(k: &str, v: i32) -> Self {
+            Self {
+                id: 0,
+                k: k.to_string(),
+                v,
+            }
+        }
+    }
+
+    #[cfg(feature = "integration_test")]
+    impl FromRow for TestRow {
+        fn from_row_opt(row: mysql::Row) -> Result<Self, mysql::FromRowError> {
+            let (id, k, v) = mysql::from_row_opt(row)?;
+            Ok(Self { id, k, v })
+        }
+    }
+
+    #[cfg(feature = "integration_test")]
+    impl SqlModel for TestRow {
+        fn equal(&self, other: &Self) -> bool {
+            self == other
+        }
+
+        fn equal_without_id(&self, other: &Self) -> bool {
+            self.k == other.k && self.v == other.v
+        }
+
+        fn lock() -> Result<MutexGuard<'static, DbPool>, MoreError> {
+            Ok(TEST_POOL.lock().unwrap_or_else(|err| err.into_inner()))
+        }
+
+        fn make_create_table() -> &'static str {
+            "CREATE TABLE python_comm_test_row (
+                id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+                k VARCHAR(32) NOT NULL UNIQUE,
+                v INT NOT NULL
+            ) ENGINE=InnoDB"
+        }
+
+        fn make_fields_b() -> &'static str {
+            "`id`, `k`, `v`"
+        }
+
+        fn make_fields_bi() -> &'static str {
+            "`k`, `v`"
+        }
+
+        fn make_fields_e() -> &'static str {
+            "id=:id, k=:k, v=:v"
+        }
+
+        fn make_fields_ei() -> &'static str {
+            "k=:k, v=:v"
+        }
+
+        fn make_fields_p() -> &'static str {
+            ":id, :k, :v"
+        }
+
+        fn make_fields_pi() -> &'static str {
+            ":k, :v"
+        }
+
+        fn make_fields_q() -> &'static str {
+            "\"id\", \"k\", \"v\""
+        }
+
+        fn make_fields_qc() -> &'static str {
+            "\"id\", \"k\", \"v\","
+        }
+
+        fn make_fields_v(&self) -> Params {
+            params! { "id" => self.id, "k" => &self.k, "v" => self.v }
+        }
+
+        fn make_fields_vi(&self) -> Params {
+            params! { "k" => &self.k, "v" => self.v }
+        }
+
+        fn table_name() -> &'static str {
+            "python_comm_test_row"
+        }
+    }
+
+    #[cfg(feature = "integration_test")]
+    #[test]
+    #[ignore = "需要 MySQL"]
+    fn test_by_id() {
+        let _guard = TestRow::reset();
+
+        let id = TestRow::new("a", 1).create().unwrap().unwrap();
+        TestRow::new("b", 2).create().unwrap();
+
+        let row = TestRow::select_by_id(id).unwrap().unwrap();
+        assert_eq!(row.id, id);
+        assert!(row.equal_without_id(&TestRow::new("a", 1)));
+
+        TestRow::delete_by_id(id).unwrap();
+        assert!(TestRow::select_by_id(id).unwrap().is_none());
+        assert_eq!(TestRow::select_count("", Params::Empty).unwrap(), 1);
+    }
+
+    #[cfg(feature = "integration_test")]
+    #[test]
+    #[ignore = "需要 MySQL"]
+    fn test_exec_scalar() {
+        let mut pool = test_pool();
+        pool.ping().unwrap();
+
+        assert_eq!(pool.exec_scalar::<i32>("SELECT 1 + 1", Params::Empty).unwrap(), Some(2));
+        assert_eq!(
+            pool.exec_scalar::<String>("SELECT :s", params! { "s" => "abc" })
+                .unwrap(),
+            Some("abc".to_string())
+        );
+        assert_eq!(
+            pool.exec_scalar::<i32>("SELECT 1 FROM DUAL WHERE 1 = 0", Params::Empty)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            pool.get_count(
+                "SELECT COUNT(*) FROM information_schema.tables WHERE 1 = 0",
+                Params::Empty
+            )
+            .unwrap(),
+            0
+        );
+        assert!(
+            pool.get_count("SELECT COUNT(*) FROM information_schema.tables", Params::Empty)
+                .unwrap()
+                > 0
+        );
+    }
+
+    #[cfg(feature = "integration_test")]
+    #[test]
+    #[ignore = "需要 MySQL"]
+    fn test_exists() {
+        let _guard = TestRow::reset();
+
+        assert!(!TestRow::exists("1 = 1", Params::Empty).unwrap());
+        TestRow::new("a", 1).create().unwrap();
+        assert!(TestRow::exists("`k` = :k", params! { "k" => "a" }).unwrap());
+        assert!(!TestRow::exists("`k` = :k", params! { "k" => "b" }).unwrap());
+        assert!(!TestRow::exists("`k` = :k AND `v` = :v", params! { "k" => "a", "v" => 2 }).unwrap());
+    }
+
+    #[cfg(feature = "integration_test")]
+    #[test]
+    #[ignore = "需要 MySQL"]
+    fn test_raw_exec() {
+        let _guard = TestRow::reset();
+
+        let tables = TestRow::raw_exec::<String>("SHOW TABLES", Params::Empty).unwrap();
+        assert!(!tables.is_empty());
+        assert!(tables.contains(&"python_comm_test_row".to_string()));
+
+        TestRow::raw_exec_drop("ALTER TABLE python_comm_test_row ADD COLUMN w INT", Params::Empty).unwrap();
+        let columns = TestRow::raw_exec::<(String, String)>(
+            "SELECT column_name, data_type FROM information_schema.columns
+                WHERE table_schema = DATABASE() AND table_name = :t ORDER BY ordinal_position",
+            params! { "t" => TestRow::table_name() },
+        )
+        .unwrap();
+        assert_eq!(columns.len(), 4);
+        assert_eq!(columns[3].0, "w");
+    }
+
+    #[cfg(feature = "integration_test")]
+    #[test]
+    #[ignore = "需要 MySQL"]
+    fn test_select_page() {
+        let _guard = TestRow::reset();
+
+        let rows = (0..25)
+            .map(|i| TestRow::new(&format!("k{:02}", i), i))
+            .collect::<Vec<_>>();
+        TestRow::create_batch(&rows).unwrap();
+
+        let mut found = Vec::new();
+        for (page, len) in [(0, 10), (1, 10), (2, 5), (3, 0)] {
+            let (rows, total) = TestRow::select_page("ORDER BY `v`", Params::Empty, page, 10).unwrap();
+            assert_eq!(rows.len(), len);
+            assert_eq!(total, 25);
+            found.extend(rows.into_iter().map(|row| row.v));
+        }
+        assert_eq!(found, (0..25).collect::<Vec<_>>());
+
+        let (rows, total) = TestRow::select_page("WHERE `v` >= :v ORDER BY `v`", params! { "v" => 20 }, 0, 10).unwrap();
+        assert_eq!((rows.len(), total), (5, 5));
+    }
+
+    #[cfg(feature = "integration_test")]
+    #[test]
+    #[ignore = "需要 MySQL"]
+    fn test_transaction() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let fname = "test_transaction";
+        let mut pool = test_pool();
+        let count = "SELECT COUNT(*) FROM python_comm_test_tx";
+        pool.get_nothing(
+            "CREATE TABLE IF NOT EXISTS python_comm_test_tx (id INT PRIMARY KEY) ENGINE=InnoDB",
+            Params::Empty,
+        )
+        .unwrap();
+        pool.get_nothing("DELETE FROM python_comm_test_tx", Params::Empty)
+            .unwrap();
+
+        // 成功时 commit
+        let value = pool
+            .transaction(|tx| {
+                tx.exec_drop("INSERT INTO python_comm_test_tx VALUES (1), (2)", ())
+                    .m(m!(fname))?;
+                Ok(5)
+            })
+            .unwrap();
+        assert_eq!(value, 5);
+        assert_eq!(pool.get_count(count, Params::Empty).unwrap(), 2);
+
+        // 失败时 rollback
+        let result: Result<(), MoreError> = pool.transaction(|tx| {
+            tx.exec_drop("INSERT INTO python_comm_test_tx VALUES (3)", ()).unwrap();
+            m!(fname, "abort", "result")
+        });
+        assert!(result.unwrap_err().to_string().contains("abort"));
+        assert_eq!(pool.get_count(count, Params::Empty).unwrap(), 2);
+
+        // panic 时 rollback
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            pool.transaction(|tx| -> Result<(), MoreError> {
+                tx.exec_drop("INSERT INTO python_comm_test_tx VALUES (4)", ()).unwrap();
+                panic!("abort");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(pool.get_count(count, Params::Empty).unwrap(), 2);
+
+        pool.get_nothing("DROP TABLE python_comm_test_tx", Params::Empty)
+            .unwrap();
+
+        // SqlModel::with_transaction
+        let _guard = TestRow::reset();
+        let insert = |k: &str| format!("INSERT INTO {} (k, v) VALUES ('{}', 1)", TestRow::table_name(), k);
+        TestRow::with_transaction(|tx| tx.exec_drop(insert("a"), ()).m(m!(fname))).unwrap();
+        assert_eq!(TestRow::select_count("", Params::Empty).unwrap(), 1);
+
+        let result: Result<(), MoreError> = TestRow::with_transaction(|tx| {
+            tx.exec_drop(insert("b"), ()).unwrap();
+            m!(fname, "abort", "result")
+        });
+        assert!(result.unwrap_err().to_string().contains("abort"));
+        assert_eq!(TestRow::select_count("", Params::Empty).unwrap(), 1);
+    }
+
+    #[cfg(feature = "integration_test")]
+    #[test]
+    #[ignore = "需要 MySQL"]
+    fn test_upsert() {
+        let _guard = TestRow::reset();
+
+        TestRow::new("a", 1).upsert().unwrap();
+        TestRow::new("b", 2).upsert().unwrap();
+        // 唯一键 k 冲突, 更新而不是报错
+        TestRow::new("a", 3).upsert().unwrap();
+
+        let rows = TestRow::select_some("ORDER BY `k`", Params::Empty).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].equal_without_id(&TestRow::new("a", 3)));
+        assert!(rows[1].equal_without_id(&TestRow::new("b", 2)));
+        assert!(TestRow::new("a", 4).create().is_err());
+    }
 }
@@ -2,8 +2,17 @@ use crate::use_m::*;
 use ahash::AHashMap;
 use lazy_static::lazy_static;
 use python_comm_macros::auto_func_name;
+#[cfg(feature = "use_rayon")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, mem::take, sync::Mutex};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env, fmt, fs,
+    mem::{size_of, take},
+    path::Path,
+    sync::Mutex,
+};
+use unicode_normalization::{char::canonical_combining_class, UnicodeNormalization};
 
 /// 关键字查找节点
 ///
@@ -21,6 +30,15 @@ struct KeywordNode {
 
     /// 蓝色节点
     is_blue: bool,
+
+    /// 仅用于蓝色节点, 权重, 缺省为 0, 为 0 时不保存以兼容旧文件
+    #[serde(default, skip_serializing_if = "is_zero")]
+    weight: u32,
+}
+
+/// serde 用, 判断 weight 是否为 0
+fn is_zero(weight: &u32) -> bool {
+    *weight == 0
 }
 
 impl KeywordNode {
@@ -37,6 +55,7 @@ impl KeywordNode {
             length,
             name: String::new(),
             is_blue: false,
+            weight: 0,
         }
     }
 
@@ -71,6 +90,25 @@ mod keyword_node_test {
     }
 }
 
+/// Unicode 规范化方式, 用于统一不同来源的相同字符, 比如预组合的 é 与 e + 组合重音符
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NormalizeMode {
+    /// 不规范化
+    #[default]
+    None,
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl NormalizeMode {
+    /// 是否不规范化
+    fn is_none(&self) -> bool {
+        *self == NormalizeMode::None
+    }
+}
+
 /// Full text matching / replacement based on aho Corasick algorithm
 ///
 /// ## Aho–Corasick 算法
@@ -157,6 +195,10 @@ mod keyword_node_test {
 ///
 /// Step4.  ts.match_() / ts.subst();  // ts 可复用
 ///
+/// 如需 Unicode 规范化, 在 Step2 之前设置: ts = TextSearcher::new().normalize(NormalizeMode::Nfc);
+///
+/// 如需按单词边界匹配, 比如 cat 不匹配 category, 设置: ts = TextSearcher::new().word_boundary(true);
+///
 /// ```
 /// use python_comm::use_basic::TextSearcher;
 ///
@@ -191,6 +233,7 @@ mod keyword_node_test {
 /// );
 /// ```
 ///
+#[derive(Clone)]
 pub struct TextSearcher {
     // 节点
     nodes: Vec<KeywordNode>,
@@ -200,17 +243,41 @@ pub struct TextSearcher {
 
     // 蓝色箭头, node -> node
     blues: AHashMap<usize, usize>,
+
+    // Unicode 规范化方式
+    normalize: NormalizeMode,
+
+    // 是否要求匹配位置前后不是字母或数字
+    word_boundary: bool,
 }
 
 impl TextSearcher {
-    /// 添加关键字
+    /// 添加关键字, 空关键字忽略
     pub fn add_keyword(&mut self, keyword: String, name: Option<String>) {
+        self.add_keyword_weighted(keyword, name, 0);
+    }
+
+    /// 同 add_keyword, 但 keyword 以字符序列提供
+    pub fn add_keyword_chars<I>(&mut self, chars: I, name: Option<String>)
+    where
+        I: IntoIterator<Item = char>,
+    {
+        self.add_keyword(chars.into_iter().collect(), name);
+    }
+
+    /// 同 add_keyword, 同时设置权重, 见 match_weighted
+    pub fn add_keyword_weighted(&mut self, keyword: String, name: Option<String>, weight: u32) {
+        // 空关键字会把根节点设为蓝色, 导致每个字符都匹配
+        if keyword.is_empty() {
+            return;
+        }
+
         // 从根节点出发
         let mut node_id = 1;
 
         // 构造 keyword 的每个节点
         let mut letters = Vec::new();
-        for letter in keyword.chars() {
+        for letter in self.normalize_str(&keyword).chars() {
             letters.push(letter);
             if let Some(&next_node_id) = self.blacks.get(&(node_id, letter)) {
                 // 存在, 继续
@@ -228,6 +295,7 @@ impl TextSearcher {
         // 设为蓝色节点
         let node = &mut self.nodes[node_id - 1];
         node.is_blue = true;
+        node.weight = weight;
 
         // 用 name 或 keyword 命名
         if let Some(name) = name {
@@ -237,6 +305,52 @@ impl TextSearcher {
         }
     }
 
+    /// 批量添加关键字 (关键字, 名称), 并创建蓝色箭头, 之后无需再调用 create_blues
+    pub fn add_keywords_bulk<I, S>(&mut self, keywords: I)
+    where
+        I: IntoIterator<Item = (S, Option<S>)>,
+        S: Into<String>,
+    {
+        for (keyword, name) in keywords {
+            self.add_keyword(keyword.into(), name.map(Into::into));
+        }
+        self.create_blues();
+    }
+
+    /// 每个字符的起始字节位置, 末尾附加 text.len(), 用于字符位置 -> 字节位置
+    fn byte_offsets(text: &str) -> Vec<usize> {
+        let mut offsets = text.char_indices().map(|(i, _)| i).collect::<Vec<usize>>();
+        offsets.push(text.len());
+        offsets
+    }
+
+    /// 是否包含名为 keyword_name 的关键字, 找到第一个即返回
+    pub fn contains_keyword(&self, text: &str, keyword_name: &str) -> bool {
+        // 仅 word_boundary 时需要原始字符
+        let text_letters = if self.word_boundary {
+            text.chars().collect::<Vec<char>>()
+        } else {
+            Vec::new()
+        };
+        let is_hit = |node_id: usize, start: usize, end: usize| {
+            self.nodes[node_id - 1].name == keyword_name && self.is_word_bounded(&text_letters, start, end)
+        };
+
+        let mut found = false;
+        match self.normalize_text(text) {
+            // 位置换算回原始 text
+            Some((letters, starts, ends)) => self.visit_letters(letters, |node_id, start, end| {
+                found = is_hit(node_id, starts[start], ends[end]);
+                !found
+            }),
+            None => self.visit_letters(text.chars(), |node_id, start, end| {
+                found = is_hit(node_id, start, end);
+                !found
+            }),
+        }
+        found
+    }
+
     /// 创建蓝色箭头
     pub fn create_blues(&mut self) {
         // 遍历每个节点
@@ -256,6 +370,20 @@ impl TextSearcher {
         }
     }
 
+    /// 按行查找, 返回 行号 (从 1 开始) -> 该行的查找结果, 位置相对于行首
+    pub fn find_lines(&self, text: &str) -> HashMap<usize, Vec<(String, usize, usize)>> {
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, self.match_(line)))
+            .filter(|(_, found)| !found.is_empty())
+            .collect()
+    }
+
+    /// 从 TextSearcherForSerde 构造, 见 to_serde
+    pub fn from_serde(tfs: TextSearcherForSerde) -> Self {
+        tfs.to()
+    }
+
     /// 获取关键字在 tree 中的位置
     fn get_node_by_keyword(&self, keyword: &[char]) -> usize {
         // 从根节点出发
@@ -274,98 +402,164 @@ impl TextSearcher {
         return node_id;
     }
 
+    /// 检查 [start, end) 前后是否为单词边界, 未设置 word_boundary 时总是 true
+    fn is_word_bounded(&self, letters: &[char], start: usize, end: usize) -> bool {
+        !self.word_boundary
+            || ((start == 0 || !letters[start - 1].is_alphanumeric())
+                && (end >= letters.len() || !letters[end].is_alphanumeric()))
+    }
+
     #[auto_func_name]
     pub fn load(text: String) -> Result<Self, MoreError> {
         Ok(serde_json::from_str::<TextSearcherForSerde>(&text).m(m!(fname))?.to())
     }
 
+    /// 从文件加载, 见 save_file
+    #[auto_func_name]
+    pub fn load_file(path: impl AsRef<Path>) -> Result<Self, MoreError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).f(m!(fname, || format!("path={}", path.display())))?;
+        Self::load(text).m(m!(fname))
+    }
+
+    /// 从 MessagePack 加载, 见 save_msgpack
+    #[cfg(feature = "use_msgpack")]
+    #[auto_func_name]
+    pub fn load_msgpack(bytes: &[u8]) -> Result<Self, MoreError> {
+        Ok(rmp_serde::from_slice::<TextSearcherForSerde>(bytes).m(m!(fname))?.to())
+    }
+
     /// 查找
     pub fn match_(&self, text: &str) -> Vec<(String, usize, usize)> {
-        // 从 root 出发
-        let mut names = Vec::new();
-        let mut node_id = 1;
-        let mut posy = 0;
+        self.match_nodes(text)
+            .into_iter()
+            .map(|(node_id, start, end)| (self.nodes[node_id - 1].name(), start, end))
+            .collect()
+    }
 
-        // 遍历每个字符
-        for letter in text.chars() {
-            posy += 1;
-            loop {
-                // 沿黑色或蓝色箭头前进
-                let (next_node_id, used) = self.move_front(node_id, letter);
-                node_id = next_node_id;
-                let node = &self.nodes[node_id - 1];
-                // 输出蓝色节点
-                if node.is_blue {
-                    if used {
-                        // 含当前字符
-                        names.push((node.name(), posy - node.length, posy));
-                    } else {
-                        // 不含当前字符
-                        names.push((node.name(), posy - node.length - 1, posy - 1));
-                    }
-                }
-                // 下一个字符
-                if used {
-                    break;
-                }
-            }
+    /// 查找, 只返回出现过的关键字名, 去重, 按名称排序
+    pub fn match_all_keywords(&self, text: &str) -> Vec<String> {
+        let mut names = self.match_unique(text);
+        names.sort();
+        names
+    }
+
+    /// 查找, 返回的位置按字节计算, 可直接用于 &text[start..end]
+    pub fn match_bytes(&self, text: &str) -> Vec<(String, usize, usize)> {
+        let offsets = Self::byte_offsets(text);
+
+        self.match_(text)
+            .into_iter()
+            .map(|(name, start, end)| (name, offsets[start], offsets[end]))
+            .collect()
+    }
+
+    /// 查找, 返回 关键字名 -> 出现次数
+    pub fn match_count(&self, text: &str) -> AHashMap<String, usize> {
+        let mut counts = AHashMap::new();
+        for (name, _, _) in self.match_(text) {
+            *counts.entry(name).or_insert(0) += 1;
         }
+        counts
+    }
 
+    /// 在已规范化的字符上查找, 返回蓝色节点及位置
+    fn match_letters<I>(&self, letters: I) -> Vec<(usize, usize, usize)>
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let mut names = Vec::new();
+        self.visit_letters(letters, |node_id, start, end| {
+            names.push((node_id, start, end));
+            true
+        });
         names
     }
 
+    /// 按行查找, 每行返回 (整行, 该行最后一个匹配的位置), 位置相对于行首, 没有匹配的行不返回
     pub fn match_line(&self, text: &str) -> Vec<(String, usize, usize)> {
-        // 从 root 出发
-        let mut names = Vec::new();
-        let mut name = String::new();
-        let mut found = (false, 0, 0);
-        let mut node_id = 1;
-        let mut posy = 0;
+        text.split(&['\r', '\n'][..])
+            .filter_map(|line| {
+                self.match_nodes(line)
+                    .last()
+                    .map(|&(_, start, end)| (line.to_string(), start, end))
+            })
+            .collect()
+    }
 
-        // 遍历每个字符
-        for letter in text.chars() {
-            if letter == '\r' || letter == '\n' {
-                // 输出
-                if found.0 {
-                    names.push((name, found.1, found.2));
-                }
-                // 重置
-                name = String::new();
-                found = (false, 0, 0);
-                node_id = 1;
-                posy = 0;
-                continue;
-            } else {
-                name.push(letter);
-                posy += 1;
-            }
-            loop {
-                // 沿黑色或蓝色箭头前进
-                let (next_node_id, used) = self.move_front(node_id, letter);
-                node_id = next_node_id;
-                let node = &self.nodes[node_id - 1];
-                // 输出蓝色节点
-                if node.is_blue {
-                    if used {
-                        // 含当前字符
-                        found = (true, posy - node.length, posy);
-                    } else {
-                        // 不含当前字符
-                        found = (true, posy - node.length - 1, posy - 1);
-                    }
-                }
-                // 下一个字符
-                if used {
-                    break;
-                }
-            }
+    /// 查找, 返回蓝色节点及位置, 位置相对于原始 text
+    fn match_nodes(&self, text: &str) -> Vec<(usize, usize, usize)> {
+        let nodes = match self.normalize_text(text) {
+            // 位置换算回原始 text
+            Some((letters, starts, ends)) => self
+                .match_letters(letters)
+                .into_iter()
+                .map(|(node_id, start, end)| (node_id, starts[start], ends[end]))
+                .collect(),
+            None => self.match_letters(text.chars()),
+        };
+
+        // 去掉不满足单词边界的
+        if self.word_boundary {
+            let letters = text.chars().collect::<Vec<char>>();
+            nodes
+                .into_iter()
+                .filter(|(_, start, end)| self.is_word_bounded(&letters, *start, *end))
+                .collect()
+        } else {
+            nodes
         }
+    }
 
-        if found.0 {
-            names.push((name, found.1, found.2));
-        }
+    /// 并行查找多个 text, 结果顺序与 texts 一致
+    #[cfg(feature = "use_rayon")]
+    pub fn match_par<'a>(&'a self, texts: &'a [&str]) -> Vec<Vec<(String, usize, usize)>> {
+        texts.par_iter().map(|text| self.match_(text)).collect()
+    }
 
-        names
+    /// 查找, 返回 text 中匹配的部分, 而不是关键字名, 位置按字节计算
+    pub fn match_slices<'a>(&self, text: &'a str) -> Vec<(&'a str, usize, usize)> {
+        let offsets = Self::byte_offsets(text);
+
+        self.match_nodes(text)
+            .into_iter()
+            .map(|(_, start, end)| (&text[offsets[start]..offsets[end]], offsets[start], offsets[end]))
+            .collect()
+    }
+
+    /// 查找, 只返回关键字名, 去重, 按首次出现的顺序
+    pub fn match_unique(&self, text: &str) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.match_(text)
+            .into_iter()
+            .filter_map(|(name, _, _)| if seen.insert(name.clone()) { Some(name) } else { None })
+            .collect()
+    }
+
+    /// 查找, 同时返回关键字的权重, 见 add_keyword_weighted
+    pub fn match_weighted(&self, text: &str) -> Vec<(String, usize, usize, u32)> {
+        self.match_nodes(text)
+            .into_iter()
+            .map(|(node_id, start, end)| {
+                let node = &self.nodes[node_id - 1];
+                (node.name(), start, end, node.weight)
+            })
+            .collect()
+    }
+
+    /// 查找, 同时返回匹配位置前后各 context_chars 个字符范围内的文本, 位置按字符计算
+    pub fn match_with_context(&self, text: &str, context_chars: usize) -> Vec<(String, usize, usize, String)> {
+        let offsets = Self::byte_offsets(text);
+        let size = offsets.len() - 1;
+
+        self.match_(text)
+            .into_iter()
+            .map(|(name, start, end)| {
+                let from = offsets[start.saturating_sub(context_chars)];
+                let to = offsets[end.saturating_add(context_chars).min(size)];
+                (name, start, end, text[from..to].to_string())
+            })
+            .collect()
     }
 
     /// 沿黑色或蓝色箭头前进
@@ -397,7 +591,59 @@ impl TextSearcher {
             nodes: vec![KeywordNode::new(Vec::new())],
             blacks: AHashMap::new(),
             blues: AHashMap::new(),
+            normalize: NormalizeMode::None,
+            word_boundary: false,
+        }
+    }
+
+    /// 设置 Unicode 规范化方式, 须在 add_keyword 之前设置, 返回的位置仍相对于原始 text
+    pub fn normalize(mut self, normalize: NormalizeMode) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// 按 normalize 规范化字符串
+    fn normalize_str(&self, text: &str) -> String {
+        match self.normalize {
+            NormalizeMode::None => text.to_string(),
+            NormalizeMode::Nfc => text.nfc().collect(),
+            NormalizeMode::Nfd => text.nfd().collect(),
+            NormalizeMode::Nfkc => text.nfkc().collect(),
+            NormalizeMode::Nfkd => text.nfkd().collect(),
+        }
+    }
+
+    /// 按 normalize 规范化 text, 不规范化时返回 None
+    ///
+    /// 返回 (规范化后的字符, 第 i 个字符对应的原始起始位置, 前 i 个字符对应的原始结束位置)
+    ///
+    /// text 按组合字符序列分段规范化, 因此规范化后的每个位置都能换算回原始位置
+    fn normalize_text(&self, text: &str) -> Option<(Vec<char>, Vec<usize>, Vec<usize>)> {
+        if self.normalize.is_none() {
+            return None;
+        }
+
+        let chars = text.chars().collect::<Vec<char>>();
+        let mut letters = Vec::new();
+        let mut starts = Vec::new();
+        let mut ends = vec![0];
+
+        // 每个 starter 字符开始一个新分段
+        let mut start = 0;
+        for end in 1..=chars.len() {
+            if end == chars.len() || canonical_combining_class(chars[end]) == 0 {
+                let segment = chars[start..end].iter().collect::<String>();
+                for letter in self.normalize_str(&segment).chars() {
+                    letters.push(letter);
+                    starts.push(start);
+                    ends.push(end);
+                }
+                start = end;
+            }
         }
+        starts.push(chars.len());
+
+        Some((letters, starts, ends))
     }
 
     #[auto_func_name]
@@ -405,17 +651,179 @@ impl TextSearcher {
         serde_json::to_string(&TextSearcherForSerde::from(self)).m(m!(fname))
     }
 
+    /// 保存到文件, 先写入 .tmp 文件再 rename, 避免中途崩溃导致文件损坏
+    #[auto_func_name]
+    pub fn save_file(&self, path: impl AsRef<Path>) -> Result<(), MoreError> {
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("tmp");
+
+        fs::write(&tmp_path, self.save().m(m!(fname))?).f(m!(fname, || format!("path={}", tmp_path.display())))?;
+        fs::rename(&tmp_path, path).f(m!(fname, || format!("path={}", path.display())))
+    }
+
+    /// 保存为 MessagePack, 比 save 的 json 小
+    ///
+    /// 按字段名保存, 因为 TextSearcherForSerde 有 skip_serializing_if 的字段, 按位置保存将无法加载
+    #[cfg(feature = "use_msgpack")]
+    #[auto_func_name]
+    pub fn save_msgpack(&self) -> Result<Vec<u8>, MoreError> {
+        rmp_serde::to_vec_named(&TextSearcherForSerde::from(self)).m(m!(fname))
+    }
+
+    /// 统计节点、箭头数量及估算的内存占用
+    pub fn stats(&self) -> TextSearcherStats {
+        // 节点, 含 letters, name 的堆内存
+        let nodes_bytes = self.nodes.capacity() * size_of::<KeywordNode>()
+            + self
+                .nodes
+                .iter()
+                .map(|node| node.letters.capacity() * size_of::<char>() + node.name.capacity())
+                .sum::<usize>();
+
+        // 箭头, 按 capacity 估算 key + value
+        let blacks_bytes = self.blacks.capacity() * size_of::<((usize, char), usize)>();
+        let blues_bytes = self.blues.capacity() * size_of::<(usize, usize)>();
+
+        TextSearcherStats {
+            node_count: self.nodes.len(),
+            black_edge_count: self.blacks.len(),
+            blue_edge_count: self.blues.len(),
+            estimated_bytes: size_of::<Self>() + nodes_bytes + blacks_bytes + blues_bytes,
+        }
+    }
+
     /// 替换
     pub fn subst(&self, text: &str) -> String {
+        self.subst_fn(text, |name, _, _| name.to_string())
+    }
+
+    /// 替换, 在每个位置取从此开始的最长关键字, 替换后从其末尾继续
+    /// 1. 不会像 subst 那样漏掉被更长前缀遮住的关键字, 如 abcd 中的 b
+    /// 2. 复杂度 O(文本长度 * 关键字长度), 慢于 subst
+    pub fn subst_all(&self, text: &str) -> String {
+        let spans = self.subst_spans_by(text, |letters| self.subst_all_spans_letters(letters));
+        Self::subst_render(text, spans, |name, _, _| name.to_string())
+    }
+
+    /// 在已规范化的字符上, 从每个位置沿黑色箭头查找最长关键字
+    fn subst_all_spans_letters(&self, letters: &[char]) -> Vec<(String, usize, usize)> {
+        let mut spans = Vec::new();
+        let mut start = 0;
+
+        while start < letters.len() {
+            // 从 root 出发, 记录最后一个蓝色节点
+            let mut node_id = 1;
+            let mut found = None;
+            for (end, letter) in letters.iter().enumerate().skip(start) {
+                match self.blacks.get(&(node_id, *letter)) {
+                    Some(&next_node_id) => node_id = next_node_id,
+                    None => break,
+                }
+                let node = &self.nodes[node_id - 1];
+                if node.is_blue && self.is_word_bounded(letters, start, end + 1) {
+                    found = Some((node.name(), start, end + 1));
+                }
+            }
+
+            match found {
+                Some(span) => {
+                    start = span.2;
+                    spans.push(span);
+                }
+                None => start += 1,
+            }
+        }
+
+        spans
+    }
+
+    /// 获取 subst 所替换的位置, 按字节计算, 用于自行按字节替换
+    pub fn subst_bytes(&self, text: &str) -> Vec<(String, usize, usize)> {
+        let offsets = Self::byte_offsets(text);
+
+        self.subst_spans(text)
+            .into_iter()
+            .map(|(name, start, end)| (name, offsets[start], offsets[end]))
+            .collect()
+    }
+
+    /// 替换, 替换内容由 f(name, start, end) 生成, start/end 按字符计算, 重叠的处理同 subst
+    pub fn subst_fn<F>(&self, text: &str, f: F) -> String
+    where
+        F: Fn(&str, usize, usize) -> String,
+    {
+        Self::subst_render(text, self.subst_spans(text), f)
+    }
+
+    /// 并行替换多个 text, 结果顺序与 texts 一致
+    #[cfg(feature = "use_rayon")]
+    pub fn subst_par(&self, texts: &[&str]) -> Vec<String> {
+        texts.par_iter().map(|text| self.subst(text)).collect()
+    }
+
+    /// 按 spans 替换, 替换内容由 f(name, start, end) 生成
+    fn subst_render<F>(text: &str, spans: Vec<(String, usize, usize)>, f: F) -> String
+    where
+        F: Fn(&str, usize, usize) -> String,
+    {
+        let letters = text.chars().collect::<Vec<char>>();
+        let mut result = String::new();
+        let mut posy = 0;
+
+        // 依次使用每个替换结果
+        for (name, start, end) in spans {
+            for letter in &letters[posy..start] {
+                result.push(*letter);
+            }
+            result += &f(&name, start, end);
+            posy = end;
+        }
+
+        // 使用末尾数据
+        for letter in &letters[posy..] {
+            result.push(*letter);
+        }
+
+        result
+    }
+
+    /// 获取 subst 所替换的位置, 按字符计算, 重叠的位置只保留第一个
+    fn subst_spans(&self, text: &str) -> Vec<(String, usize, usize)> {
+        self.subst_spans_by(text, |letters| self.subst_spans_letters(letters))
+    }
+
+    /// 在规范化后的 text 上用 spans_letters 计算替换位置, 并换算回原始 text
+    fn subst_spans_by<S>(&self, text: &str, spans_letters: S) -> Vec<(String, usize, usize)>
+    where
+        S: Fn(&[char]) -> Vec<(String, usize, usize)>,
+    {
+        match self.normalize_text(text) {
+            Some((letters, starts, ends)) => {
+                // 位置换算回原始 text, 同一分段内的多个结果换算后会重叠, 只保留第一个
+                let mut spans: Vec<(String, usize, usize)> = Vec::new();
+                for (name, start, end) in spans_letters(&letters) {
+                    let (start, end) = (starts[start], ends[end]);
+                    if !matches!(spans.last(), Some(last) if last.2 > start) {
+                        spans.push((name, start, end));
+                    }
+                }
+                spans
+            }
+            None => spans_letters(&text.chars().collect::<Vec<char>>()),
+        }
+    }
+
+    /// 在已规范化的字符上获取 subst 所替换的位置
+    fn subst_spans_letters(&self, letters: &[char]) -> Vec<(String, usize, usize)> {
         // 从 root 出发
-        let mut result: (String, usize) = (String::new(), 0);
-        let mut last_found: (String, usize, usize) = (String::new(), 0, 0);
+        let mut spans: Vec<(String, usize, usize)> = Vec::new();
+        let mut last_found: Option<(String, usize, usize)> = None;
         let mut node_id = 1;
         let mut posy = 0;
+        let mut used_posy = 0;
 
         // 遍历每个字符
-        let letters = text.chars().collect::<Vec<char>>();
-        for letter in &letters {
+        for letter in letters {
             posy += 1;
             loop {
                 // 沿黑色或蓝色箭头前进
@@ -429,18 +837,20 @@ impl TextSearcher {
                     } else {
                         (node.name(), posy - node.length - 1, posy - 1)
                     };
-                    if found.1 != last_found.1 {
-                        // 使用上一次的结果
-                        if last_found.1 >= result.1 {
-                            for i in result.1..last_found.1 {
-                                result.0.push(letters[i]);
+                    // 不满足单词边界的, 忽略
+                    if self.is_word_bounded(letters, found.1, found.2) {
+                        if let Some(last) = last_found.take() {
+                            if found.1 != last.1 {
+                                // 使用上一次的结果
+                                if last.1 >= used_posy {
+                                    used_posy = last.2;
+                                    spans.push(last);
+                                }
+                                // else: 两次结果有交叉, 并且第一次已经使用, 放弃第二次的
                             }
-                            result.0 += &last_found.0;
-                            result.1 = last_found.2;
                         }
-                        // else: 两次结果有交叉, 并且第一次已经使用, 放弃第二次的
+                        last_found = Some(found);
                     }
-                    last_found = found;
                 }
                 if used {
                     break;
@@ -449,20 +859,69 @@ impl TextSearcher {
         }
 
         // 使用上一次的结果
-        if last_found.2 >= last_found.1 && last_found.1 >= result.1 {
-            for i in result.1..last_found.1 {
-                result.0.push(letters[i]);
+        if let Some(last) = last_found {
+            if last.2 >= last.1 && last.1 >= used_posy {
+                spans.push(last);
             }
-            result.0 += &last_found.0;
-            result.1 = last_found.2;
         }
 
-        // 使用末尾数据
-        for letter in &letters[result.1..] {
-            result.0.push(*letter);
+        spans
+    }
+
+    /// 转换为可序列化的 TextSearcherForSerde, 用于 save/save_msgpack 以外的序列化格式
+    pub fn to_serde(&self) -> TextSearcherForSerde {
+        TextSearcherForSerde::from(self)
+    }
+
+    /// 在已规范化的字符上查找, 每找到一个蓝色节点调用 visit(node, start, end), visit 返回 false 时停止
+    fn visit_letters<I, F>(&self, letters: I, mut visit: F)
+    where
+        I: IntoIterator<Item = char>,
+        F: FnMut(usize, usize, usize) -> bool,
+    {
+        // 从 root 出发
+        let mut node_id = 1;
+        let mut posy = 0;
+
+        // 遍历每个字符
+        for letter in letters {
+            posy += 1;
+            loop {
+                // 沿黑色或蓝色箭头前进
+                let (next_node_id, used) = self.move_front(node_id, letter);
+                node_id = next_node_id;
+                let node = &self.nodes[node_id - 1];
+                // 输出蓝色节点
+                if node.is_blue {
+                    let go_on = if used {
+                        // 含当前字符
+                        visit(node_id, posy - node.length, posy)
+                    } else {
+                        // 不含当前字符
+                        visit(node_id, posy - node.length - 1, posy - 1)
+                    };
+                    if !go_on {
+                        return;
+                    }
+                }
+                // 下一个字符
+                if used {
+                    break;
+                }
+            }
         }
+    }
+
+    /// 设置是否按单词边界匹配, 匹配位置前后的字符不能是字母或数字
+    pub fn word_boundary(mut self, word_boundary: bool) -> Self {
+        self.word_boundary = word_boundary;
+        self
+    }
+}
 
-        result.0
+impl Default for TextSearcher {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -519,6 +978,118 @@ mod text_searcher_test {
         );
     }
 
+    #[test]
+    fn test_add_keyword_chars() {
+        let mut ts = TextSearcher::new();
+        ts.add_keyword_chars("abcdef".chars().take(3), None);
+        ts.add_keyword_chars(['北', '京'], Some("city".to_string()));
+        ts.create_blues();
+
+        assert_eq!(
+            ts.match_("xabc北京"),
+            [("abc".to_string(), 1, 4), ("city".to_string(), 4, 6)]
+        );
+    }
+
+    #[test]
+    fn test_add_keyword_empty() {
+        let mut ts = TextSearcher::new();
+        ts.add_keyword("".to_string(), None);
+        ts.add_keyword("".to_string(), Some("x".to_string()));
+        ts.add_keyword_chars([], None);
+
+        assert_eq!(ts.nodes.len(), 1);
+        assert!(!ts.nodes[0].is_blue);
+        assert!(ts.blacks.is_empty());
+
+        ts.add_keyword("ab".to_string(), None);
+        ts.create_blues();
+        assert_eq!(ts.match_("xaby"), [("ab".to_string(), 1, 3)]);
+    }
+
+    #[test]
+    fn test_add_keyword_weighted() {
+        let mut ts = TextSearcher::new();
+        ts.add_keyword_weighted("ab".to_string(), None, 3);
+        ts.add_keyword_weighted("b".to_string(), Some("B".to_string()), 5);
+        ts.add_keyword("c".to_string(), None);
+
+        assert_eq!(ts.nodes[2].weight, 3);
+        assert_eq!(ts.nodes[3].weight, 5);
+        assert_eq!(ts.nodes[4].weight, 0);
+    }
+
+    #[test]
+    fn test_add_keywords_bulk() {
+        let keywords = (0..100)
+            .map(|i| {
+                (
+                    format!("k{}", i),
+                    if i % 2 == 0 { Some(format!("n{}", i)) } else { None },
+                )
+            })
+            .collect::<Vec<_>>();
+        let text = (0..120).map(|i| format!("k{} ", i * 7 % 130)).collect::<String>();
+
+        let mut ts1 = TextSearcher::new();
+        for (keyword, name) in keywords.clone() {
+            ts1.add_keyword(keyword, name);
+        }
+        ts1.create_blues();
+
+        let mut ts2 = TextSearcher::new();
+        ts2.add_keywords_bulk(keywords);
+
+        assert_eq!(ts2.nodes.len(), ts1.nodes.len());
+        assert!(!ts1.match_(&text).is_empty());
+        assert_eq!(ts2.match_(&text), ts1.match_(&text));
+
+        let mut ts3 = TextSearcher::new();
+        ts3.add_keywords_bulk([("a", None), ("b", Some("B"))]);
+        assert_eq!(ts3.match_("a b"), [("a".to_string(), 0, 1), ("B".to_string(), 2, 3)]);
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut ts1 = TextSearcher::new().word_boundary(true);
+        for keyword in &["cat", "dog", "猫"] {
+            ts1.add_keyword(keyword.to_string(), None);
+        }
+        ts1.create_blues();
+        let ts2 = ts1.clone();
+
+        let text = "a cat, a dog, a 猫 and a tomcat";
+        let (result1, result2) = std::thread::scope(|s| {
+            let h1 = s.spawn(|| ts1.match_(text));
+            let h2 = s.spawn(|| ts2.match_(text));
+            (h1.join().unwrap(), h2.join().unwrap())
+        });
+        assert_eq!(result1.len(), 3);
+        assert_eq!(result1, result2);
+    }
+
+    #[test]
+    fn test_contains_keyword() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["cat", "dog", "猫"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.add_keyword("kitten".to_string(), Some("cat".to_string()));
+        ts.create_blues();
+
+        assert!(ts.contains_keyword("a dog and a kitten", "cat"));
+        assert!(ts.contains_keyword("a dog and a kitten", "dog"));
+        assert!(ts.contains_keyword("一只猫", "猫"));
+        assert!(!ts.contains_keyword("a dog and a kitten", "kitten"));
+        assert!(!ts.contains_keyword("a bird", "cat"));
+
+        let mut ts = TextSearcher::new().word_boundary(true).normalize(NormalizeMode::Nfkc);
+        ts.add_keyword("cat".to_string(), None);
+        ts.create_blues();
+        assert!(!ts.contains_keyword("tomcat", "cat"));
+        assert!(ts.contains_keyword("tomcat ｃａｔ", "cat"));
+    }
+
     #[test]
     fn test_create_blues() {
         let mut ts = TextSearcher::new();
@@ -532,6 +1103,32 @@ mod text_searcher_test {
         assert_eq!(blues, [(3, 4), (5, 2), (6, 3), (7, 9), (8, 10), (10, 2), (11, 2)]);
     }
 
+    #[test]
+    fn test_default() {
+        let mut ts = TextSearcher::default();
+        ts.add_keyword("ab".to_string(), None);
+        ts.create_blues();
+        assert_eq!(ts.match_("xaby"), [("ab".to_string(), 1, 3)]);
+
+        assert!(TextSearcher::default().match_("ab").is_empty());
+    }
+
+    #[test]
+    fn test_find_lines() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["abc", "def"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.create_blues();
+
+        let lines = ts.find_lines("...\n.abc.def\r\n\n---def---\n...\nabc");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[&2], [("abc".to_string(), 1, 4), ("def".to_string(), 5, 8)]);
+        assert_eq!(lines[&4], [("def".to_string(), 3, 6)]);
+        assert_eq!(lines[&6], [("abc".to_string(), 0, 3)]);
+        assert!(ts.find_lines("").is_empty());
+    }
+
     #[test]
     fn test_get_node_by_keyword() {
         let mut ts = TextSearcher::new();
@@ -610,6 +1207,65 @@ mod text_searcher_test {
         );
     }
 
+    #[test]
+    fn test_match_all_keywords() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["dog", "cat", "bird"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.add_keyword("kitten".to_string(), Some("cat".to_string()));
+        ts.create_blues();
+
+        assert_eq!(ts.match_all_keywords("kitten, dog, cat, dog"), ["cat", "dog"]);
+        assert!(ts.match_all_keywords("fish").is_empty());
+    }
+
+    #[test]
+    fn test_match_bytes() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["北京", "😀ab", "c"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.create_blues();
+
+        let text = "我爱北京😀abc!";
+        let found = ts.match_bytes(text);
+        assert_eq!(
+            found,
+            [
+                ("北京".to_string(), 6, 12),
+                ("😀ab".to_string(), 12, 18),
+                ("c".to_string(), 18, 19)
+            ]
+        );
+        for (name, start, end) in found {
+            assert_eq!(&text[start..end], name);
+        }
+    }
+
+    #[test]
+    fn test_match_count() {
+        let mut ts = TextSearcher::new();
+        ts.add_keyword("ab".to_string(), None);
+        ts.create_blues();
+
+        let counts = ts.match_count("aababab");
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts["ab"], 3);
+
+        let mut ts = TextSearcher::new();
+        for keyword in &["a", "ab"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.create_blues();
+
+        let counts = ts.match_count("aababab");
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["a"], 4);
+        assert_eq!(counts["ab"], 3);
+        assert!(ts.match_count("xyz").is_empty());
+    }
+
     #[test]
     fn test_match_line() {
         let mut ts = TextSearcher::new();
@@ -625,7 +1281,113 @@ mod text_searcher_test {
                 ("---def---".to_string(), 3, 6),
                 ("abc".to_string(), 0, 3)
             ]
-        )
+        );
+
+        // 与 match_ 一样支持 normalize 和 word_boundary
+        let mut ts = TextSearcher::new().normalize(NormalizeMode::Nfc).word_boundary(true);
+        ts.add_keyword("caf\u{e9}".to_string(), None);
+        ts.create_blues();
+        assert_eq!(
+            ts.match_line("a cafe\u{301}\ncafe\u{301}s\r\ncaf\u{e9}"),
+            [("a cafe\u{301}".to_string(), 2, 7), ("caf\u{e9}".to_string(), 0, 4)]
+        );
+    }
+
+    #[cfg(feature = "use_rayon")]
+    #[test]
+    fn test_match_par() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["a", "ab", "bab", "bc", "bca", "c", "caa"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.create_blues();
+
+        let owned = (0..10000)
+            .map(|i| format!("{}abccab{}", i, i % 7))
+            .collect::<Vec<String>>();
+        let texts = owned.iter().map(|text| text.as_str()).collect::<Vec<&str>>();
+        let expected = texts.iter().map(|text| ts.match_(text)).collect::<Vec<_>>();
+        assert_eq!(ts.match_par(&texts), expected);
+    }
+
+    #[test]
+    fn test_match_slices() {
+        let mut ts = TextSearcher::new().normalize(NormalizeMode::Nfc);
+        for keyword in &["北京", "café"] {
+            ts.add_keyword(keyword.to_string(), Some("X".to_string()));
+        }
+        ts.create_blues();
+
+        let text = "北京 cafe\u{301}!";
+        assert_eq!(ts.match_slices(text), [("北京", 0, 6), ("cafe\u{301}", 7, 13)]);
+    }
+
+    #[test]
+    fn test_match_unique() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["a", "b", "ab"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.add_keyword("c".to_string(), Some("B".to_string()));
+        ts.add_keyword("d".to_string(), Some("B".to_string()));
+        ts.create_blues();
+
+        assert_eq!(ts.match_("abcabc").len(), 8);
+        assert_eq!(ts.match_unique("abcabc"), ["a", "ab", "b", "B"]);
+        assert_eq!(ts.match_unique("dcb"), ["B", "b"]);
+        assert!(ts.match_unique("xyz").is_empty());
+    }
+
+    #[test]
+    fn test_match_weighted() {
+        let mut ts = TextSearcher::new();
+        ts.add_keyword_weighted("ab".to_string(), None, 3);
+        ts.add_keyword_weighted("b".to_string(), Some("B".to_string()), 5);
+        ts.add_keyword("c".to_string(), None);
+        ts.create_blues();
+
+        assert_eq!(
+            ts.match_weighted("xabc"),
+            [
+                ("ab".to_string(), 1, 3, 3),
+                ("B".to_string(), 2, 3, 5),
+                ("c".to_string(), 3, 4, 0)
+            ]
+        );
+
+        // 权重可保存, 为 0 时不保存
+        let text = ts.save().unwrap();
+        assert_eq!(text.matches("weight").count(), 2);
+        let ts = TextSearcher::load(text).unwrap();
+        assert_eq!(ts.match_weighted("ab")[0], ("ab".to_string(), 0, 2, 3));
+    }
+
+    #[test]
+    fn test_match_with_context() {
+        let mut ts = TextSearcher::new();
+        ts.add_keyword("北京".to_string(), None);
+        ts.add_keyword("ab".to_string(), None);
+        ts.create_blues();
+
+        assert_eq!(
+            ts.match_with_context("北京欢迎你, 来自 ab 的朋友", 2),
+            [
+                ("北京".to_string(), 0, 2, "北京欢迎".to_string()),
+                ("ab".to_string(), 10, 12, "自 ab 的".to_string())
+            ]
+        );
+        assert_eq!(
+            ts.match_with_context("你好北京", 3),
+            [("北京".to_string(), 2, 4, "你好北京".to_string())]
+        );
+        assert_eq!(
+            ts.match_with_context("ab", 0),
+            [("ab".to_string(), 0, 2, "ab".to_string())]
+        );
+        assert_eq!(
+            ts.match_with_context("你好北京", usize::MAX),
+            [("北京".to_string(), 2, 4, "你好北京".to_string())]
+        );
     }
 
     #[test]
@@ -638,6 +1400,81 @@ mod text_searcher_test {
         assert_eq!(ts.nodes[0].to_string(), "[]/0, , false");
     }
 
+    #[test]
+    fn test_normalize() {
+        let composed = "caf\u{e9}";
+        let decomposed = "cafe\u{301}";
+
+        // 不规范化, 无法互相匹配
+        let mut ts = TextSearcher::new();
+        ts.add_keyword(composed.to_string(), None);
+        ts.create_blues();
+        assert_eq!(ts.match_(decomposed), []);
+
+        // 规范化后, 位置相对于原始 text
+        for mode in [
+            NormalizeMode::Nfc,
+            NormalizeMode::Nfd,
+            NormalizeMode::Nfkc,
+            NormalizeMode::Nfkd,
+        ] {
+            let mut ts = TextSearcher::new().normalize(mode);
+            ts.add_keyword(composed.to_string(), Some("X".to_string()));
+            ts.create_blues();
+
+            assert_eq!(ts.match_(&format!("a {} b", decomposed)), [("X".to_string(), 2, 7)]);
+            assert_eq!(ts.match_(&format!("a {} b", composed)), [("X".to_string(), 2, 6)]);
+            assert_eq!(ts.subst(&format!("a {} b", decomposed)), "a X b");
+            assert_eq!(ts.subst_bytes(decomposed), [("X".to_string(), 0, 6)]);
+
+            let ts = TextSearcher::load(ts.save().unwrap()).unwrap();
+            assert_eq!(ts.subst(composed), "X");
+        }
+    }
+
+    #[test]
+    fn test_save_load_file() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["bcdef", "defghi", "hijk"] {
+            ts.add_keyword(keyword.to_string(), Some(format!("x{}y", keyword)));
+        }
+        ts.create_blues();
+
+        let path = std::env::temp_dir().join("python_comm_test_save_load_file.json");
+        ts.save_file(&path).unwrap();
+        assert!(!path.with_extension("tmp").exists());
+
+        let ts2 = TextSearcher::load_file(&path).unwrap();
+        assert_eq!(ts2.match_("abcdefghijklmn"), ts.match_("abcdefghijklmn"));
+        assert_eq!(ts2.subst("abcdefghijklmn"), "axbcdefygxhijkylmn");
+
+        fs::remove_file(&path).unwrap();
+        assert!(TextSearcher::load_file(&path).is_err());
+    }
+
+    #[cfg(feature = "use_msgpack")]
+    #[test]
+    fn test_save_load_msgpack() {
+        for ts in [TextSearcher::new(), TextSearcher::new().word_boundary(true)] {
+            let mut ts = ts;
+            for keyword in &["bcdef", "defghi", "hijk", "北京"] {
+                ts.add_keyword(keyword.to_string(), Some(format!("x{}y", keyword)));
+            }
+            ts.create_blues();
+
+            let bytes = ts.save_msgpack().unwrap();
+            assert!(bytes.len() < ts.save().unwrap().len());
+
+            let ts2 = TextSearcher::load_msgpack(&bytes).unwrap();
+            assert_eq!(ts2.stats().node_count, ts.stats().node_count);
+            assert_eq!(ts2.stats().blue_edge_count, ts.stats().blue_edge_count);
+            assert_eq!(ts2.match_("abcdefghijklmn 北京"), ts.match_("abcdefghijklmn 北京"));
+            assert_eq!(ts2.subst("abcdefghijklmn 北京"), ts.subst("abcdefghijklmn 北京"));
+        }
+
+        assert!(TextSearcher::load_msgpack(b"not msgpack").is_err());
+    }
+
     #[test]
     fn test_serde() {
         let mut ts = TextSearcher::new();
@@ -715,6 +1552,23 @@ mod text_searcher_test {
         assert_eq!(ts.nodes[5].to_string(), "[]/3, bab!, true");
     }
 
+    #[test]
+    fn test_stats() {
+        let mut ts = TextSearcher::new();
+        let empty = ts.stats();
+        for keyword in &["a", "ab", "bab"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.create_blues();
+
+        let stats = ts.stats();
+        assert_eq!(stats.node_count, 6);
+        assert_eq!(stats.black_edge_count, 5);
+        assert_eq!(stats.blue_edge_count, 3);
+        assert!(stats.estimated_bytes > empty.estimated_bytes);
+        assert!(stats.to_string().starts_with("nodes=6, blacks=5, blues=3, bytes="));
+    }
+
     #[test]
     fn test_subst1() {
         let mut ts = TextSearcher::new();
@@ -747,6 +1601,137 @@ mod text_searcher_test {
 
         assert_eq!(ts.subst("abdpkz"), "a_keyword_z");
     }
+
+    #[test]
+    fn test_subst_all() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["ab", "abc"] {
+            ts.add_keyword(keyword.to_string(), Some(keyword.to_uppercase()));
+        }
+        ts.create_blues();
+
+        // ab 与 abc 起点相同, 两者都取较长的 abc
+        assert_eq!(ts.subst("xabcy"), "xABCy");
+        assert_eq!(ts.subst_all("xabcy"), "xABCy");
+
+        let mut ts = TextSearcher::new();
+        for keyword in &["abcd", "b", "cd"] {
+            ts.add_keyword(keyword.to_string(), Some(keyword.to_uppercase()));
+        }
+        ts.create_blues();
+
+        // subst 沿 abcd 前进时漏掉 b, subst_all 在每个位置重新查找
+        assert_eq!(ts.subst("xabcey"), "xabcey");
+        assert_eq!(ts.subst_all("xabcey"), "xaBcey");
+        assert_eq!(ts.subst_all("abcd bcd"), "ABCD BCD");
+    }
+
+    #[test]
+    fn test_subst_bytes() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["欢迎", "迎你", "🎉"] {
+            ts.add_keyword(keyword.to_string(), Some(format!("<{}>", keyword)));
+        }
+        ts.create_blues();
+
+        let text = "北京欢迎你🎉";
+        let found = ts.subst_bytes(text);
+        assert_eq!(found, [("<欢迎>".to_string(), 6, 12), ("<🎉>".to_string(), 15, 19)]);
+        assert_eq!(&text[6..12], "欢迎");
+        assert_eq!(&text[15..19], "🎉");
+        assert_eq!(ts.subst(text), "北京<欢迎>你<🎉>");
+    }
+
+    #[test]
+    fn test_subst_fn() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["bcdef", "defghi", "hijk"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.create_blues();
+
+        // 计数替换
+        let count = std::cell::Cell::new(0);
+        let text = ts.subst_fn("abcdefghijklmn", |_, _, _| {
+            count.set(count.get() + 1);
+            format!("#{}", count.get())
+        });
+        assert_eq!(text, "a#1g#2lmn");
+
+        // 变长、变短, 与 subst 的结果一致
+        assert_eq!(
+            ts.subst_fn("abcdefghijklmn", |name, start, end| format!(
+                "<{}:{}-{}>",
+                name, start, end
+            )),
+            "a<bcdef:1-6>g<hijk:7-11>lmn"
+        );
+        assert_eq!(ts.subst_fn("abcdefghijklmn", |_, _, _| String::new()), "aglmn");
+        assert_eq!(
+            ts.subst_fn("abcdefghijklmn", |name, _, _| name.to_string()),
+            ts.subst("abcdefghijklmn")
+        );
+    }
+
+    #[cfg(feature = "use_rayon")]
+    #[test]
+    fn test_subst_par() {
+        let mut ts = TextSearcher::new();
+        for keyword in &["bcdef", "defghi", "hijk"] {
+            ts.add_keyword(keyword.to_string(), None);
+        }
+        ts.create_blues();
+
+        let owned = (0..10000)
+            .map(|i| format!("{}abcdefghijklmn{}", i, i % 7))
+            .collect::<Vec<String>>();
+        let texts = owned.iter().map(|text| text.as_str()).collect::<Vec<&str>>();
+        let expected = texts.iter().map(|text| ts.subst(text)).collect::<Vec<_>>();
+        assert_eq!(ts.subst_par(&texts), expected);
+    }
+
+    #[cfg(feature = "use_msgpack")]
+    #[test]
+    fn test_to_serde() {
+        let mut ts = TextSearcher::new().word_boundary(true);
+        for keyword in &["cat", "dog", "猫"] {
+            ts.add_keyword(keyword.to_string(), Some(keyword.to_uppercase()));
+        }
+        ts.create_blues();
+
+        let bytes = rmp_serde::to_vec_named(&ts.to_serde()).unwrap();
+        let ts2 = TextSearcher::from_serde(rmp_serde::from_slice(&bytes).unwrap());
+
+        let text = "a cat, a dog, a 猫 and a tomcat";
+        assert_eq!(ts.match_(text).len(), 3);
+        assert_eq!(ts2.match_(text), ts.match_(text));
+    }
+
+    #[test]
+    fn test_word_boundary() {
+        let mut ts = TextSearcher::new().word_boundary(true);
+        for keyword in &["cat", "猫"] {
+            ts.add_keyword(keyword.to_string(), Some("X".to_string()));
+        }
+        ts.create_blues();
+
+        assert_eq!(ts.match_("I have a cat."), [("X".to_string(), 9, 12)]);
+        assert_eq!(ts.match_("cat"), [("X".to_string(), 0, 3)]);
+        assert_eq!(ts.match_("category"), []);
+        assert_eq!(ts.match_("tomcat"), []);
+        assert_eq!(ts.match_("cat2 猫 a猫"), [("X".to_string(), 5, 6)]);
+
+        assert_eq!(
+            ts.subst("I have a cat, not a tomcat or category."),
+            "I have a X, not a tomcat or category."
+        );
+
+        // 不设置时, 全部匹配
+        let mut ts = TextSearcher::new();
+        ts.add_keyword("cat".to_string(), Some("X".to_string()));
+        ts.create_blues();
+        assert_eq!(ts.subst("a cat, tomcat, category"), "a X, tomX, Xegory");
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -754,6 +1739,10 @@ pub struct TextSearcherForSerde {
     nodes: Vec<KeywordNode>,
     blacks: Vec<((usize, char), usize)>,
     blues: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "NormalizeMode::is_none")]
+    normalize: NormalizeMode,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    word_boundary: bool,
 }
 
 impl TextSearcherForSerde {
@@ -762,6 +1751,8 @@ impl TextSearcherForSerde {
             nodes: ts.nodes.clone(),
             blacks: ts.blacks.iter().map(|(&k, &v)| (k, v)).collect(),
             blues: ts.blues.iter().map(|(&k, &v)| (k, v)).collect(),
+            normalize: ts.normalize,
+            word_boundary: ts.word_boundary,
         }
     }
 
@@ -770,37 +1761,111 @@ impl TextSearcherForSerde {
             nodes: self.nodes,
             blacks: self.blacks.iter().map(|&x| x).collect(),
             blues: self.blues.iter().map(|&x| x).collect(),
+            normalize: self.normalize,
+            word_boundary: self.word_boundary,
         }
     }
 }
 
+/// TextSearcher 统计, 用于调试及评估是否需要拆分关键字
+#[derive(Debug)]
+pub struct TextSearcherStats {
+    /// 节点数, 含根节点
+    pub node_count: usize,
+
+    /// 黑色箭头数
+    pub black_edge_count: usize,
+
+    /// 蓝色箭头数
+    pub blue_edge_count: usize,
+
+    /// 估算的内存占用, 字节
+    pub estimated_bytes: usize,
+}
+
+impl fmt::Display for TextSearcherStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "nodes={}, blacks={}, blues={}, bytes={}",
+            self.node_count, self.black_edge_count, self.blue_edge_count, self.estimated_bytes
+        )
+    }
+}
+
 pub struct TextSearcherManager {
     /// ts 总数
     count: i32,
 
+    /// 最大 ts 数, 超出时淘汰最久未使用的
+    max_capacity: usize,
+
     /// tsid -> ts
     tss: HashMap<i32, TextSearcher>,
+
+    /// tsid, 按使用先后排序, 最久未使用的在前
+    lru: VecDeque<i32>,
 }
 
 impl TextSearcherManager {
     /// 添加 ts
     pub fn add_text_searcher(&mut self, tsid: i32, ts: TextSearcher) {
-        self.tss.insert(tsid, ts);
+        self.insert(tsid, ts);
     }
 
     /// 获取 ts
     #[auto_func_name]
     pub fn get_text_searcher(&mut self, tsid: i32) -> Result<TextSearcher, MoreError> {
+        self.lru.retain(|&x| x != tsid);
         self.tss
             .remove(&tsid)
             .ok_or_else(|| m!(fname, &format!("指定的 TextSearcher={} 无效", tsid), "more"))
     }
 
-    /// 构造
+    /// 保存 ts, 超出 max_capacity 时淘汰最久未使用的
+    #[auto_func_name]
+    fn insert(&mut self, tsid: i32, ts: TextSearcher) {
+        self.lru.retain(|&x| x != tsid);
+        self.lru.push_back(tsid);
+        self.tss.insert(tsid, ts);
+
+        while self.tss.len() > self.max_capacity {
+            let old_tsid = match self.lru.pop_front() {
+                Some(old_tsid) => old_tsid,
+                None => break,
+            };
+            self.tss.remove(&old_tsid);
+            eprintln!(
+                "{}",
+                m!(
+                    fname,
+                    &format!(
+                        "TextSearcher 超过 {} 个, 淘汰 TextSearcher={}",
+                        self.max_capacity, old_tsid
+                    ),
+                    "more"
+                )
+            );
+        }
+    }
+
+    /// 构造, 最大 ts 数取自环境变量 PYTHON_COMM_TSM_CAPACITY, 缺省 1000
     fn new() -> Self {
+        Self::new_with_capacity(
+            env::var("PYTHON_COMM_TSM_CAPACITY")
+                .ok()
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(1000),
+        )
+    }
+
+    /// 构造, 指定最大 ts 数, 至少为 1, 否则新建的 ts 立即被淘汰
+    pub fn new_with_capacity(max_capacity: usize) -> Self {
         Self {
             count: 0,
+            max_capacity: max_capacity.max(1),
             tss: HashMap::new(),
+            lru: VecDeque::new(),
         }
     }
 
@@ -809,22 +1874,68 @@ impl TextSearcherManager {
         self.count += 1;
 
         let mut ts = TextSearcher::new();
-        for (keyword, name) in keywords {
-            ts.add_keyword(keyword, name);
-        }
-        ts.create_blues();
+        ts.add_keywords_bulk(keywords);
 
-        self.tss.insert(self.count, ts);
+        self.insert(self.count, ts);
 
         self.count
     }
 
     /// 删除 ts
     pub fn remove_text_searcher(&mut self, tsid: i32) {
+        self.lru.retain(|&x| x != tsid);
         self.tss.remove(&tsid);
     }
 }
 
+impl Default for TextSearcherManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod text_searcher_manager_test {
+    use super::*;
+
+    #[test]
+    fn test_capacity() {
+        let mut tsm = TextSearcherManager::new_with_capacity(2);
+        let keywords = vec![("a".to_string(), None)];
+
+        // 超出容量, 淘汰最早的
+        let tsid1 = tsm.new_text_searcher(keywords.clone());
+        let tsid2 = tsm.new_text_searcher(keywords.clone());
+        let tsid3 = tsm.new_text_searcher(keywords.clone());
+        assert_eq!(tsm.tss.len(), 2);
+        assert!(tsm.get_text_searcher(tsid1).is_err());
+
+        // 使用过的 tsid2 不被淘汰
+        let ts = tsm.get_text_searcher(tsid2).unwrap();
+        tsm.add_text_searcher(tsid2, ts);
+        let tsid4 = tsm.new_text_searcher(keywords);
+        assert!(tsm.get_text_searcher(tsid3).is_err());
+        assert!(tsm.get_text_searcher(tsid2).is_ok());
+        assert!(tsm.get_text_searcher(tsid4).is_ok());
+
+        // 0 按 1 处理
+        let mut tsm = TextSearcherManager::new_with_capacity(0);
+        let tsid = tsm.new_text_searcher(vec![("a".to_string(), None)]);
+        assert!(tsm.get_text_searcher(tsid).is_ok());
+    }
+
+    #[test]
+    fn test_default() {
+        let mut tsm = TextSearcherManager::default();
+        let tsid = tsm.new_text_searcher(vec![("a".to_string(), None)]);
+        assert_eq!(
+            tsm.get_text_searcher(tsid).unwrap().match_("ba"),
+            [("a".to_string(), 1, 2)]
+        );
+        assert!(tsm.max_capacity > 0);
+    }
+}
+
 // 定义全局变量 GLOBALS
 lazy_static! {
     static ref TSM: Mutex<TextSearcherManager> = Mutex::new(TextSearcherManager::new());
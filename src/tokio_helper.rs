@@ -3,20 +3,63 @@ use {
         future::Future,
         pin::Pin,
         task::Poll::{Pending, Ready},
+        time::Duration,
+    },
+    tokio::{
+        macros::support::poll_fn,
+        sync::Semaphore,
+        time::{sleep, timeout},
     },
-    tokio::macros::support::poll_fn,
 };
 
 // std::future::poll_fn 功能相同, 但在某些 rustc 上是非 stable 版本才能用
 
+/// 同 join_all, 但同时最多运行 concurrency 个任务, 返回值按 tasks 顺序排列
+/// 1. 任务取得 permit 后才调用 tasks[i]() 创建 future, 完成后释放 permit
+/// 2. concurrency 为 0 时按 1 处理
+pub async fn bounded_join_all<F, Fut>(tasks: Vec<F>, concurrency: usize) -> Vec<Fut::Output>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future,
+{
+    let semaphore = Semaphore::new(concurrency.max(1));
+    let semaphore = &semaphore;
+
+    let futures = tasks
+        .into_iter()
+        .map(|task| async move {
+            // semaphore 不会 close, 可安全 unwrap
+            let _permit = semaphore.acquire().await.unwrap();
+            task().await
+        })
+        .collect();
+
+    join_all(futures).await
+}
+
 /// 从 join! 改造而来, 等待 futures 全部完成
 /// 1. Pin::new_unchecked 是否正确存疑
 pub async fn join_all<F>(mut futures: Vec<F>) -> Vec<F::Output>
 where
     F: Future,
 {
-    // 初始化, poll_fn 内是一个 poll 函数, 会被执行多次, 每次从不同的 future 开始检查
     let mut results: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+
+    poll_all(&mut futures, &mut results).await;
+
+    // 此时必然全部 Some, 可安全 unwrap
+    results.into_iter().map(|x| x.unwrap()).collect()
+}
+
+/// 从 join! 改造而来, 等待 futures 全部完成, 按完成顺序 reduce 结果
+/// 1. Pin::new_unchecked 是否正确存疑
+pub async fn join_all_and_reduce<F, R, A, B>(mut futures: Vec<F>, reduce: R, reduce_args: &A, init_value: &mut B)
+where
+    F: Future,
+    R: Fn(&mut B, F::Output, &A, usize) -> (),
+{
+    // 初始化, poll_fn 内是一个 poll 函数, 会被执行多次, 每次从不同的 future 开始检查
+    let mut results: Vec<bool> = futures.iter().map(|_| false).collect();
     let size = futures.len();
     let mut first = 0;
 
@@ -33,7 +76,7 @@ where
             let pos = (first + i) % size;
 
             // 已经完成的, 不能再次 poll
-            if result_refs[pos].is_some() {
+            if result_refs[pos] {
                 continue;
             }
 
@@ -46,7 +89,8 @@ where
                     is_pending = true;
                 }
                 Ready(result) => {
-                    result_refs[pos] = Some(result);
+                    result_refs[pos] = true;
+                    reduce(init_value, result, reduce_args, pos);
                 }
             }
         }
@@ -61,20 +105,146 @@ where
         }
     })
     .await;
+}
+
+/// 同 join_all, 全部 Ok 时返回按输入顺序排列的 Ok 值, 否则返回全部 Err
+pub async fn join_all_results<F, T, E>(futures: Vec<F>) -> Result<Vec<T>, Vec<E>>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let mut oks = Vec::new();
+    let mut errs = Vec::new();
+
+    for result in join_all(futures).await {
+        match result {
+            Ok(value) => oks.push(value),
+            Err(err) => errs.push(err),
+        }
+    }
+
+    if errs.is_empty() {
+        Ok(oks)
+    } else {
+        Err(errs)
+    }
+}
+
+/// 同 join_all, 但最多等待 duration
+/// 1. 全部完成返回 Ok, 超时返回 Err, 其中未完成的为 None
+/// 2. 超时后剩余 futures 不再 poll, 但也没有 kill
+pub async fn join_all_timeout<F>(
+    mut futures: Vec<F>,
+    duration: Duration,
+) -> Result<Vec<F::Output>, Vec<Option<F::Output>>>
+where
+    F: Future,
+{
+    let mut results: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+
+    match timeout(duration, poll_all(&mut futures, &mut results)).await {
+        // 此时必然全部 Some, 可安全 unwrap
+        Ok(()) => Ok(results.into_iter().map(|x| x.unwrap()).collect()),
+        Err(_) => Err(results),
+    }
+}
+
+/// 同 join_all, 每完成一个 future 调用 on_done(已完成数量, 总数量)
+/// 1. on_done 在 poll 过程中调用, 不能阻塞
+pub async fn join_all_with_progress<F, C>(futures: Vec<F>, on_done: C) -> Vec<F::Output>
+where
+    F: Future,
+    C: Fn(usize, usize),
+{
+    let mut state: (Vec<Option<F::Output>>, usize) = (futures.iter().map(|_| None).collect(), 0);
+
+    let reduce = |state: &mut (Vec<Option<F::Output>>, usize), result, on_done: &C, pos| {
+        state.0[pos] = Some(result);
+        state.1 += 1;
+        on_done(state.1, state.0.len());
+    };
+    join_all_and_reduce(futures, reduce, &on_done, &mut state).await;
 
     // 此时必然全部 Some, 可安全 unwrap
-    results.into_iter().map(|x| x.unwrap()).collect()
+    state.0.into_iter().map(|x| x.unwrap()).collect()
 }
 
-/// 从 join! 改造而来, 等待 futures 全部完成, 按完成顺序 reduce 结果
+/// 等待 futures 中第一个完成的, 返回其结果及其余 futures 的结果(未完成的为 None)
+/// 1. 剩余 futures 不再 poll, 但也没有 kill
+/// 2. futures 不能为空
+pub async fn join_first<F>(futures: Vec<F>) -> (F::Output, Vec<Option<F::Output>>)
+where
+    F: Future,
+{
+    let (_, mut results) = join_to_happy(futures, |_, _| true, &()).await;
+
+    // 第一个完成即结束, 此时只有一个 Some
+    let first = results.iter_mut().find_map(|x| x.take()).expect("futures 不能为空");
+    (first, results)
+}
+
+/// 等待 futures 中第一个返回 Ok 的, 返回其结果及其余 futures 的结果(未完成的为 None)
+/// 1. 全部返回 Err 时, 返回 futures 中第一个 Err
+/// 2. futures 不能为空
+pub async fn join_first_ok<F, T, E>(futures: Vec<F>) -> Result<(T, Vec<Option<Result<T, E>>>), E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let is_happy = |results: &Vec<Option<Result<T, E>>>, _: &()| results.iter().any(|x| matches!(x, Some(Ok(_))));
+    let (happy, mut results) = join_to_happy(futures, is_happy, &()).await;
+
+    if happy {
+        // 第一个 Ok 即结束, 此时只有一个 Ok
+        let pos = results.iter().position(|x| matches!(x, Some(Ok(_)))).unwrap();
+        match results[pos].take() {
+            Some(Ok(first)) => Ok((first, results)),
+            _ => unreachable!(),
+        }
+    } else {
+        // 此时全部为 Some(Err)
+        match results.into_iter().next().expect("futures 不能为空") {
+            Some(Err(err)) => Err(err),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// 等待 futures 中的 n 个完成, 结束全部 futures
+/// 1. 返回值 (a, b)
+///     1. a: futures 的全部返回值, 未完成的为 None
+///     2. b: 是否已有 n 个完成, n 大于 futures 数量时为 false
+/// 2. 剩余 futures 不再 poll, 但也没有 kill
+pub async fn join_n_of_m<F>(futures: Vec<F>, n: usize) -> (Vec<Option<F::Output>>, bool)
+where
+    F: Future,
+{
+    if n == 0 {
+        return (futures.iter().map(|_| None).collect(), true);
+    }
+
+    let is_happy = |results: &Vec<Option<F::Output>>, _: &()| results.iter().filter(|x| x.is_some()).count() >= n;
+    let (happy, results) = join_to_happy(futures, is_happy, &()).await;
+
+    (results, happy)
+}
+
+/// 从 join! 改造而来, futures 中的部分任务完成后, 如果 is_happy() 返回 true, 结束全部 futures
 /// 1. Pin::new_unchecked 是否正确存疑
-pub async fn join_all_and_reduce<F, R, A, B>(mut futures: Vec<F>, reduce: R, reduce_args: &A, init_value: &mut B)
+/// 2. 如果 futures 中有 spawn 返回的``句柄'', 只是结束这个句柄, spawn 内的代码继续执行
+/// 3. 返回值 (a, b)
+///     1. a: is_happy() 的最终返回值
+///     2. b: futures 的全部返回值, 未完成的为 None
+/// 4. is_happy 为 FnMut, 可以在多次调用间累积状态
+pub async fn join_to_happy<F, H, A>(
+    mut futures: Vec<F>,
+    mut is_happy: H,
+    happy_args: &A,
+) -> (bool, Vec<Option<F::Output>>)
 where
     F: Future,
-    R: Fn(&mut B, F::Output, &A, usize) -> (),
+    H: FnMut(&Vec<Option<F::Output>>, &A) -> bool,
 {
     // 初始化, poll_fn 内是一个 poll 函数, 会被执行多次, 每次从不同的 future 开始检查
-    let mut results: Vec<bool> = futures.iter().map(|_| false).collect();
+    let mut results: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
     let size = futures.len();
     let mut first = 0;
 
@@ -82,7 +252,7 @@ where
     let future_refs = &mut futures;
     let result_refs = &mut results;
 
-    poll_fn(move |cx| {
+    let happy = poll_fn(move |cx| {
         // 记录本次 poll_fn 的成果
         let mut is_pending = false;
 
@@ -91,7 +261,7 @@ where
             let pos = (first + i) % size;
 
             // 已经完成的, 不能再次 poll
-            if result_refs[pos] {
+            if result_refs[pos].is_some() {
                 continue;
             }
 
@@ -104,8 +274,10 @@ where
                     is_pending = true;
                 }
                 Ready(result) => {
-                    result_refs[pos] = true;
-                    reduce(init_value, result, reduce_args, pos);
+                    result_refs[pos] = Some(result);
+                    if is_happy(result_refs, happy_args) {
+                        return Ready(true);
+                    }
                 }
             }
         }
@@ -115,34 +287,29 @@ where
             first = (first + 1) % size;
             Pending
         } else {
-            // 已经全部完成
-            Ready(())
+            // 已经全部完成, 但是 is_happy() 不满足
+            Ready(false)
         }
     })
     .await;
+
+    (happy, results)
 }
 
-/// 从 join! 改造而来, futures 中的部分任务完成后, 如果 is_happy() 返回 true, 结束全部 futures
-/// 1. Pin::new_unchecked 是否正确存疑
-/// 2. 如果 futures 中有 spawn 返回的``句柄'', 只是结束这个句柄, spawn 内的代码继续执行
-/// 3. 返回值 (a, b)
-///     1. a: is_happy() 的最终返回值
-///     2. b: futures 的全部返回值, 未完成的为 None
-pub async fn join_to_happy<F, H, A>(mut futures: Vec<F>, is_happy: H, happy_args: &A) -> (bool, Vec<Option<F::Output>>)
+/// join_all 的 poll 部分, 结果写入 results, 中途被 drop 时 results 保留已完成的部分
+async fn poll_all<F>(futures: &mut [F], results: &mut [Option<F::Output>])
 where
     F: Future,
-    H: Fn(&Vec<Option<F::Output>>, &A) -> bool,
 {
     // 初始化, poll_fn 内是一个 poll 函数, 会被执行多次, 每次从不同的 future 开始检查
-    let mut results: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
     let size = futures.len();
     let mut first = 0;
 
     // 改为引用, 这样可以多次执行 poll_fn + move
-    let future_refs = &mut futures;
-    let result_refs = &mut results;
+    let future_refs = futures;
+    let result_refs = results;
 
-    let happy = poll_fn(move |cx| {
+    poll_fn(move |cx| {
         // 记录本次 poll_fn 的成果
         let mut is_pending = false;
 
@@ -165,9 +332,6 @@ where
                 }
                 Ready(result) => {
                     result_refs[pos] = Some(result);
-                    if is_happy(result_refs, happy_args) {
-                        return Ready(true);
-                    }
                 }
             }
         }
@@ -177,11 +341,245 @@ where
             first = (first + 1) % size;
             Pending
         } else {
-            // 已经全部完成, 但是 is_happy() 不满足
-            Ready(false)
+            // 已经全部完成
+            Ready(())
         }
     })
     .await;
+}
 
-    (happy, results)
+/// 重试 make_future(attempt) 直到返回 Ok, 最多 max_attempts 次, 返回最后一次的结果
+/// 1. attempt 从 0 开始, 第 attempt 次失败后等待 backoff(attempt) 再重试
+/// 2. max_attempts 为 0 时按 1 处理
+pub async fn with_retry<F, Fut, T, E, B>(make_future: F, max_attempts: usize, backoff: B) -> Result<T, E>
+where
+    F: Fn(usize) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    B: Fn(usize) -> Duration,
+{
+    let mut attempt = 0;
+    loop {
+        match make_future(attempt).await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt + 1 >= max_attempts => return Err(err),
+            Err(_) => {
+                sleep(backoff(attempt)).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        std::{
+            cell::{Cell, RefCell},
+            rc::Rc,
+            sync::atomic::{AtomicUsize, Ordering},
+            task::{Context, Poll},
+        },
+    };
+
+    /// ms 毫秒后返回 value
+    async fn delay<T>(ms: u64, value: T) -> T {
+        sleep(Duration::from_millis(ms)).await;
+        value
+    }
+
+    /// 第 ready_at 次 poll 时完成, 并累加 completed
+    struct PollCounter {
+        polls: usize,
+        ready_at: usize,
+        completed: Rc<Cell<usize>>,
+    }
+
+    impl Future for PollCounter {
+        type Output = usize;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<usize> {
+            self.polls += 1;
+            if self.polls < self.ready_at {
+                cx.waker().wake_by_ref();
+                return Pending;
+            }
+
+            self.completed.set(self.completed.get() + 1);
+            Ready(self.ready_at)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bounded_join_all() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let (running, max_running) = (&running, &max_running);
+
+        let tasks = (0..10)
+            .map(|i| {
+                move || async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    sleep(Duration::from_millis(10 - i)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    i
+                }
+            })
+            .collect();
+
+        assert_eq!(bounded_join_all(tasks, 2).await, (0..10).collect::<Vec<_>>());
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+        assert_eq!(running.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_join_all_results() {
+        let futures = vec![delay(20, Ok::<i32, String>(1)), delay(0, Ok(2)), delay(10, Ok(3))];
+        assert_eq!(join_all_results(futures).await, Ok(vec![1, 2, 3]));
+
+        let futures = vec![
+            delay(20, Err::<i32, String>("a".to_string())),
+            delay(0, Err("b".to_string())),
+        ];
+        assert_eq!(
+            join_all_results(futures).await,
+            Err(vec!["a".to_string(), "b".to_string()])
+        );
+
+        let futures = vec![delay(0, Ok(1)), delay(10, Err("b".to_string())), delay(0, Ok(3))];
+        assert_eq!(join_all_results(futures).await, Err(vec!["b".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_join_all_timeout() {
+        let futures = vec![delay(0, 1), delay(10, 2), delay(0, 3)];
+        assert_eq!(
+            join_all_timeout(futures, Duration::from_secs(5)).await,
+            Ok(vec![1, 2, 3])
+        );
+
+        let futures = vec![delay(0, 1), delay(5000, 2), delay(10, 3), delay(5000, 4)];
+        let results = join_all_timeout(futures, Duration::from_millis(200)).await;
+        assert_eq!(results, Err(vec![Some(1), None, Some(3), None]));
+    }
+
+    #[tokio::test]
+    async fn test_join_all_with_progress() {
+        let calls = RefCell::new(Vec::new());
+        let futures = vec![delay(20, 1), delay(0, 2), delay(10, 3), delay(0, 4)];
+        let results = join_all_with_progress(futures, |done, total| calls.borrow_mut().push((done, total))).await;
+        assert_eq!(results, vec![1, 2, 3, 4]);
+        assert_eq!(calls.into_inner(), vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+    }
+
+    #[tokio::test]
+    async fn test_join_first() {
+        let futures = vec![delay(5000, 1), delay(10, 2), delay(5000, 3)];
+        assert_eq!(join_first(futures).await, (2, vec![None, None, None]));
+    }
+
+    #[tokio::test]
+    async fn test_join_first_ok() {
+        let futures = vec![delay(5000, Ok(1)), delay(0, Err("a".to_string())), delay(10, Ok(3))];
+        let (first, results) = join_first_ok(futures).await.unwrap();
+        assert_eq!(first, 3);
+        assert_eq!(results, vec![None, Some(Err("a".to_string())), None]);
+
+        let futures = vec![
+            delay(10, Err::<i32, String>("a".to_string())),
+            delay(0, Err("b".to_string())),
+        ];
+        assert_eq!(join_first_ok(futures).await, Err("a".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_join_n_of_m() {
+        let completed = Rc::new(Cell::new(0));
+        let futures = |ready_ats: &[usize]| -> Vec<PollCounter> {
+            ready_ats
+                .iter()
+                .map(|&ready_at| PollCounter {
+                    polls: 0,
+                    ready_at,
+                    completed: completed.clone(),
+                })
+                .collect()
+        };
+
+        let (results, happy) = join_n_of_m(futures(&[3, 1, 5, 2, 4]), 2).await;
+        assert!(happy);
+        assert_eq!(results, vec![None, Some(1), None, Some(2), None]);
+        assert_eq!(completed.get(), 2);
+
+        completed.set(0);
+        let (results, happy) = join_n_of_m(futures(&[2, 1]), 3).await;
+        assert!(!happy);
+        assert_eq!(results, vec![Some(2), Some(1)]);
+        assert_eq!(completed.get(), 2);
+
+        completed.set(0);
+        let (results, happy) = join_n_of_m(futures(&[1, 1]), 0).await;
+        assert!(happy);
+        assert_eq!(results, vec![None, None]);
+        assert_eq!(completed.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_join_to_happy() {
+        let completed = Rc::new(Cell::new(0));
+        let futures = [4, 1, 5, 2, 3, 6]
+            .iter()
+            .map(|&ready_at| PollCounter {
+                polls: 0,
+                ready_at,
+                completed: completed.clone(),
+            })
+            .collect();
+
+        // is_happy 在每个 future 完成时调用一次, 由捕获的计数判断, 不看 results
+        let mut calls = 0;
+        let (happy, results) = join_to_happy(
+            futures,
+            |_, target: &usize| {
+                calls += 1;
+                calls >= *target
+            },
+            &3,
+        )
+        .await;
+        assert!(happy);
+        assert_eq!(calls, 3);
+        assert_eq!(results, vec![None, Some(1), None, Some(2), Some(3), None]);
+        assert_eq!(completed.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry() {
+        let attempts = Rc::new(RefCell::new(Vec::new()));
+        let backoffs = Rc::new(RefCell::new(Vec::new()));
+        let make_future = |attempt: usize| {
+            attempts.borrow_mut().push(attempt);
+            async move {
+                match attempt {
+                    0 | 1 => Err(format!("fail {}", attempt)),
+                    _ => Ok(attempt * 10),
+                }
+            }
+        };
+        let backoff = |attempt: usize| {
+            backoffs.borrow_mut().push(attempt);
+            Duration::from_millis(1 << attempt)
+        };
+
+        assert_eq!(with_retry(make_future, 5, backoff).await, Ok(20));
+        assert_eq!(*attempts.borrow(), vec![0, 1, 2]);
+        assert_eq!(*backoffs.borrow(), vec![0, 1]);
+
+        attempts.borrow_mut().clear();
+        backoffs.borrow_mut().clear();
+        assert_eq!(with_retry(make_future, 2, backoff).await, Err("fail 1".to_string()));
+        assert_eq!(*attempts.borrow(), vec![0, 1]);
+        assert_eq!(*backoffs.borrow(), vec![0]);
+    }
 }